@@ -0,0 +1,45 @@
+use std::str::FromStr;
+
+// A square board given as text, one line per row: '.' is a free square and
+// '#' a blocked square.
+pub struct Board {
+    pub size: usize,
+    pub blocked: Vec<(usize, usize)>,
+}
+
+impl FromStr for Board {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<&str>>();
+        let size = rows.len();
+        if size == 0 {
+            return Err("the board is empty".to_string());
+        }
+
+        let mut blocked = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            if row.chars().count() != size {
+                return Err(format!(
+                    "row {} has {} squares instead of {}",
+                    i,
+                    row.chars().count(),
+                    size
+                ));
+            }
+            for (j, square) in row.chars().enumerate() {
+                match square {
+                    '.' => (),
+                    '#' => blocked.push((i, j)),
+                    _ => return Err(format!("unexpected '{}' on row {}", square, i)),
+                }
+            }
+        }
+
+        Ok(Self { size, blocked })
+    }
+}
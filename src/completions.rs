@@ -0,0 +1,184 @@
+use crate::solver::{MaskCounter, Solver};
+
+// Counts the solutions extending the queens of the first rows, under the rules
+// of a solver: its size, variant, piece, blocked squares and fixed queens. The
+// symmetry reduction is ignored, all the solutions are counted.
+// Each count is kept along with the columns and the diagonals attacked on the
+// next row, so that the queries reaching the same situation, in the same
+// search or in the following ones, do not count again.
+pub struct Completions {
+    board_size: usize,
+    // Counts the queens of every row, the squares attacked on each row being
+    // the ones which can never hold a queen: blocked, attacked by a fixed
+    // queen, or left by the fixed queen of the row
+    counter: MaskCounter,
+}
+
+impl Completions {
+    // Returns `None` for boards larger than 64, or for pieces with leaps,
+    // whose attacks do not only depend on the columns and the diagonals
+    pub fn new(solver: &Solver) -> Option<Self> {
+        let n = solver.board_size();
+        let piece = solver.piece();
+        if n > 64 || !piece.leap_offsets().is_empty() {
+            return None;
+        }
+        let mut unavailable = vec![0u64; n];
+        for (i, j) in solver.blocked_squares() {
+            unavailable[i] |= 1 << j;
+        }
+        for (i, j) in solver.fixed_queens() {
+            for (row, mask) in unavailable.iter_mut().enumerate() {
+                for column in 0..n {
+                    if row == i && column != j || row != i && solver.attack((i, j), (row, column)) {
+                        *mask |= 1 << column;
+                    }
+                }
+            }
+        }
+        // Each row is followed by the next one, but the last one
+        let rows = unavailable
+            .into_iter()
+            .enumerate()
+            .map(|(i, mask)| (mask, if i + 1 < n { 1 } else { 0 }))
+            .collect();
+        Some(Self {
+            board_size: n,
+            counter: MaskCounter::new(rows, n, solver.variant(), piece).with_memo(),
+        })
+    }
+
+    // Number of solutions whose first rows hold queens on the given columns,
+    // or `None` if these queens cannot be placed
+    pub fn count(&mut self, prefix: &[usize]) -> Option<u64> {
+        if prefix.len() > self.board_size {
+            return None;
+        }
+        let (mut cols, mut left, mut right) = (0, 0, 0);
+        for (i, &j) in prefix.iter().enumerate() {
+            if j >= self.board_size || self.counter.free(i, cols, left, right) & (1 << j) == 0 {
+                return None;
+            }
+            (cols, left, right) = self.counter.place(i, 1 << j, cols, left, right);
+        }
+        if prefix.len() == self.board_size {
+            return Some(1);
+        }
+        Some(self.counter.count(prefix.len(), cols, left, right))
+    }
+
+    // Number of solutions extending the prefix for each column of the next
+    // row, or `None` if the prefix cannot be placed or fills the board
+    pub fn next_row(&mut self, prefix: &[usize]) -> Option<Vec<u64>> {
+        self.count(prefix)?;
+        if prefix.len() == self.board_size {
+            return None;
+        }
+        let mut queens = prefix.to_vec();
+        let counts = (0..self.board_size)
+            .map(|j| {
+                queens.push(j);
+                let count = self.count(&queens).unwrap_or(0);
+                queens.pop();
+                count
+            })
+            .collect();
+        Some(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::Piece;
+    use crate::solver::{Backend, Variant};
+
+    // The solvers with each rule changing the counts, on a board of size `n`
+    fn solvers(n: usize) -> Vec<Solver> {
+        let mut solvers = Vec::new();
+        for variant in [Variant::Classic, Variant::Toroidal] {
+            for piece in [Piece::queen(), Piece::rook(), Piece::bishop()] {
+                solvers.push(
+                    Solver::with_backend(n, Backend::Vectors)
+                        .with_variant(variant)
+                        .with_piece(piece),
+                );
+            }
+        }
+        if n >= 5 {
+            let solver = Solver::with_backend(n, Backend::Vectors);
+            solvers.push(solver.clone().with_fixed_queens(&[(2, 0)]).unwrap());
+            solvers.push(solver.with_blocked_squares(&[(0, 1), (3, 3)]).unwrap());
+        }
+        solvers
+    }
+
+    // Every prefix of up to `length` queens, attacked or not
+    fn prefixes(n: usize, length: usize) -> Vec<Vec<usize>> {
+        let mut prefixes = vec![Vec::new()];
+        let mut last = vec![Vec::new()];
+        for _ in 0..length.min(n) {
+            last = last
+                .iter()
+                .flat_map(|prefix: &Vec<usize>| (0..n).map(move |j| [&prefix[..], &[j]].concat()))
+                .collect();
+            prefixes.extend(last.iter().cloned());
+        }
+        prefixes
+    }
+
+    fn solutions(mut solver: Solver) -> Vec<Vec<usize>> {
+        let mut solutions = Vec::new();
+        while let Some(placement) = solver.search() {
+            if placement.is_valid {
+                solutions.push(placement.configuration);
+            }
+        }
+        solutions
+    }
+
+    #[test]
+    fn counts_match_the_search() {
+        for n in 1..=7 {
+            for solver in solvers(n) {
+                let mut completions = Completions::new(&solver).unwrap();
+                let solutions = solutions(solver.clone());
+                // Queried twice, the second time from the kept counts
+                for _ in 0..2 {
+                    for prefix in prefixes(n, 3) {
+                        let count = completions.count(&prefix).unwrap_or(0);
+                        assert_eq!(count, solver.count_completions(&prefix).0);
+                        let extending = solutions
+                            .iter()
+                            .filter(|solution| solution.starts_with(&prefix))
+                            .count();
+                        assert_eq!(count, extending as u64, "{:?} on {}", prefix, n);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn full_and_invalid_prefixes() {
+        let solver = Solver::new(6).with_fixed_queens(&[(1, 3)]).unwrap();
+        let mut completions = Completions::new(&solver).unwrap();
+        assert_eq!(completions.count(&[]), Some(1));
+        assert_eq!(completions.count(&[1, 3, 5, 0, 2, 4]), Some(1));
+        assert_eq!(completions.count(&[1, 3, 5, 0, 4, 2]), None);
+        // Off the fixed queen, off the board, or longer than the board
+        assert_eq!(completions.count(&[1, 2]), None);
+        assert_eq!(completions.count(&[6]), None);
+        assert_eq!(completions.count(&[1, 3, 5, 0, 2, 4, 0]), None);
+        assert_eq!(completions.next_row(&[1, 3, 5, 0, 2, 4]), None);
+        assert_eq!(completions.next_row(&[]), Some(vec![0, 1, 0, 0, 0, 0]));
+        assert_eq!(completions.next_row(&[1]), Some(vec![0, 0, 0, 1, 0, 0]));
+    }
+
+    #[test]
+    fn pieces_with_leaps_are_not_counted() {
+        let solver = Solver::new(8).with_piece(Piece::superqueen());
+        assert!(Completions::new(&solver).is_none());
+        assert!(Completions::new(&Solver::new(65)).is_none());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::solver::Configuration;
+
+// Builds one solution in linear time, without any search: the queens of the
+// first rows take the even columns (counting from 1), the next ones the odd
+// columns, both in increasing order. This works unless n mod 6 is 2 or 3, in
+// which case a few columns are moved:
+// - if n mod 6 = 2, columns 1 and 3 are swapped and 5 is moved to the end,
+// - if n mod 6 = 3, column 2 is moved to the end of the even columns, and
+//   columns 1 and 3 to the end of the odd columns.
+// Returns `None` for the sizes without solution.
+pub fn solve(board_size: usize) -> Option<Configuration> {
+    let n = board_size;
+    if n == 0 || n == 2 || n == 3 {
+        return None;
+    }
+
+    let mut evens = (2..=n).step_by(2).collect::<Vec<usize>>();
+    let mut odds = (1..=n).step_by(2).collect::<Vec<usize>>();
+    match n % 6 {
+        2 => {
+            odds.swap(0, 1);
+            let five = odds.remove(2);
+            odds.push(five);
+        }
+        3 => {
+            let two = evens.remove(0);
+            evens.push(two);
+            odds.drain(..2);
+            odds.extend(&[1, 3]);
+        }
+        _ => (),
+    }
+
+    Some(Configuration {
+        configuration: evens.iter().chain(&odds).map(|column| column - 1).collect(),
+        is_valid: true,
+        symmetry_order: None,
+        rows: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn constructions_are_solutions() {
+        // Every remainder modulo 6, and a few large boards
+        for n in (1..=60).chain(996..=1001) {
+            let solver = Solver::new(n);
+            match solve(n) {
+                Some(solution) => {
+                    assert!(solver.verify(&solution.configuration), "{}", n);
+                    assert!(solution.is_valid);
+                }
+                None => assert!([2, 3].contains(&n), "{}", n),
+            }
+        }
+        assert!(solve(0).is_none());
+    }
+}
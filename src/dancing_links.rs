@@ -0,0 +1,254 @@
+// Knuth's Algorithm X with dancing links, which finds all the ways to select
+// options covering each primary item exactly once and each secondary item at
+// most once.
+// The items are numbered from 0, primary items first. Each option is labeled,
+// for instance with the square of its queen.
+// The search is a state machine giving one step at a time, like the solver.
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Step {
+    // The option with this label is selected
+    Select((usize, usize)),
+    // The option with this label is deselected
+    Deselect((usize, usize)),
+    // No other option can cover the item
+    DeadEnd(usize),
+    // The options selected cover all the primary items
+    Solution,
+    // All the ways to cover the items have been explored
+    Exhausted,
+}
+
+// The nodes are stored in vectors: the root is 0, the header of the item k is
+// k + 1, and the nodes of the options come next, option after option.
+// Only the primary items are linked to the root, the secondary items are never
+// chosen.
+#[derive(Clone)]
+pub struct DancingLinks {
+    primary: usize,
+    left: Vec<usize>,
+    right: Vec<usize>,
+    // For the headers, the number of options left in the item
+    len: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    // Header of the item of each node
+    top: Vec<usize>,
+    // Option of each node
+    option: Vec<usize>,
+    // First and last nodes of each option
+    options: Vec<(usize, usize)>,
+    labels: Vec<(usize, usize)>,
+    // Header of the item chosen at each level, and node of the option
+    // selected. The last level is the cursor: its node is the next option
+    // tried, or the header itself when none is left. Its header is the root
+    // when all the primary items are covered.
+    levels: Vec<(usize, usize)>,
+    // Number of levels at the beginning of `levels` which are never
+    // backtracked
+    root_depth: usize,
+    // Set when the last level has been fully explored
+    backtrack_next: bool,
+}
+
+impl DancingLinks {
+    pub fn new(primary: usize, secondary: usize) -> Self {
+        let items = primary + secondary;
+        let mut left = (0..=items)
+            .map(|k| k.wrapping_sub(1))
+            .collect::<Vec<usize>>();
+        let mut right = (1..=items + 1).collect::<Vec<usize>>();
+        left[0] = primary;
+        right[primary] = 0;
+        // A secondary item is only linked to itself
+        for h in primary + 1..=items {
+            left[h] = h;
+            right[h] = h;
+        }
+        Self {
+            primary,
+            left,
+            right,
+            len: vec![0; items + 1],
+            up: (0..=items).collect(),
+            down: (0..=items).collect(),
+            top: (0..=items).collect(),
+            option: vec![0; items + 1],
+            options: Vec::new(),
+            labels: Vec::new(),
+            levels: Vec::new(),
+            root_depth: 0,
+            backtrack_next: false,
+        }
+    }
+
+    // Must be called before the search starts, with distinct items
+    pub fn add_option(&mut self, label: (usize, usize), items: &[usize]) {
+        let first = self.top.len();
+        for &item in items {
+            let h = item + 1;
+            let node = self.top.len();
+            self.top.push(h);
+            self.option.push(self.options.len());
+            self.up.push(self.up[h]);
+            self.down.push(h);
+            let last = self.up[h];
+            self.down[last] = node;
+            self.up[h] = node;
+            self.len[h] += 1;
+        }
+        self.options.push((first, self.top.len() - 1));
+        self.labels.push(label);
+    }
+
+    // Selects the option with this label in all the solutions, before the
+    // search starts. Returns `false` if it cannot be selected.
+    pub fn select(&mut self, label: (usize, usize)) -> bool {
+        let option = match self.labels.iter().position(|&l| l == label) {
+            Some(option) => option,
+            None => return false,
+        };
+        let (first, last) = self.options[option];
+        // The option must not have been hidden by another one
+        if (first..=last).any(|q| self.down[self.up[q]] != q) {
+            return false;
+        }
+        for node in first..=last {
+            self.cover(self.top[node]);
+        }
+        true
+    }
+
+    // Starts the search, once all the options are given
+    pub fn start(&mut self) {
+        self.levels.clear();
+        self.backtrack_next = false;
+        let cursor = self.choose_item();
+        self.levels.push(cursor);
+    }
+
+    // The options selected so far are never backtracked
+    pub fn keep_selected(&mut self) {
+        self.root_depth = self.levels.len() - 1;
+    }
+
+    // Tries the options of the last level again, from the first one. A
+    // solution already given is left as it is.
+    pub fn restart_level(&mut self) {
+        if let Some(&(h, _)) = self.levels.last() {
+            if h == 0 {
+                return;
+            }
+            let level = self.levels.len() - 1;
+            self.levels[level] = (h, self.down[h]);
+        }
+        self.backtrack_next = false;
+    }
+
+    fn cover(&mut self, h: usize) {
+        if h <= self.primary {
+            self.right[self.left[h]] = self.right[h];
+            self.left[self.right[h]] = self.left[h];
+        }
+        let mut p = self.down[h];
+        while p != h {
+            let (first, last) = self.options[self.option[p]];
+            for q in (first..=last).filter(|&q| q != p) {
+                self.down[self.up[q]] = self.down[q];
+                self.up[self.down[q]] = self.up[q];
+                self.len[self.top[q]] -= 1;
+            }
+            p = self.down[p];
+        }
+    }
+
+    // Exactly undoes `cover`
+    fn uncover(&mut self, h: usize) {
+        let mut p = self.up[h];
+        while p != h {
+            let (first, last) = self.options[self.option[p]];
+            for q in (first..=last).rev().filter(|&q| q != p) {
+                self.down[self.up[q]] = q;
+                self.up[self.down[q]] = q;
+                self.len[self.top[q]] += 1;
+            }
+            p = self.up[p];
+        }
+        if h <= self.primary {
+            self.right[self.left[h]] = h;
+            self.left[self.right[h]] = h;
+        }
+    }
+
+    // Covers the primary item with the fewest options left, the first one if
+    // several, and returns the cursor on its first option
+    fn choose_item(&mut self) -> (usize, usize) {
+        let mut best = 0;
+        let mut h = self.right[0];
+        while h != 0 {
+            if best == 0 || self.len[h] < self.len[best] {
+                best = h;
+            }
+            h = self.right[h];
+        }
+        if best != 0 {
+            self.cover(best);
+        }
+        (best, self.down[best])
+    }
+
+    // Advances the search by one step. Once the search is exhausted, always
+    // returns `Exhausted`.
+    pub fn next_step(&mut self) -> Step {
+        let (h, node) = match self.levels.last().copied() {
+            Some(level) => level,
+            None => return Step::Exhausted,
+        };
+
+        if !self.backtrack_next {
+            if h == 0 {
+                self.backtrack_next = true;
+                return Step::Solution;
+            }
+            if node == h {
+                self.backtrack_next = true;
+                return Step::DeadEnd(h - 1);
+            }
+            let (first, last) = self.options[self.option[node]];
+            for q in (first..=last).filter(|&q| q != node) {
+                self.cover(self.top[q]);
+            }
+            let cursor = self.choose_item();
+            self.levels.push(cursor);
+            return Step::Select(self.labels[self.option[node]]);
+        }
+
+        self.backtrack_next = false;
+        match self.backtrack_once() {
+            Some(label) => Step::Deselect(label),
+            None => Step::Exhausted,
+        }
+    }
+
+    // Deselects the option of the last level and moves to the next one.
+    // Returns the label of the option deselected, if any.
+    pub fn backtrack_once(&mut self) -> Option<(usize, usize)> {
+        let (h, _) = self.levels.pop()?;
+        if h != 0 {
+            self.uncover(h);
+        }
+        if self.levels.len() <= self.root_depth {
+            self.levels.clear();
+            return None;
+        }
+        let level = self.levels.len() - 1;
+        let (h, node) = self.levels[level];
+        // The other items of the option are uncovered in reverse order
+        let (first, last) = self.options[self.option[node]];
+        for q in (first..=last).rev().filter(|&q| q != node) {
+            self.uncover(self.top[q]);
+        }
+        self.levels[level] = (h, self.down[node]);
+        Some(self.labels[self.option[node]])
+    }
+}
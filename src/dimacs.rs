@@ -0,0 +1,283 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use crate::solver::{Configuration, Solver, Variant};
+
+// Variable telling whether a queen is on (i, j), numbered from 1
+pub fn variable(board_size: usize, i: usize, j: usize) -> usize {
+    i * board_size + j + 1
+}
+
+// The rules of a solver as a CNF formula in the DIMACS format: each row holds
+// a queen, and no two queens are on the same row or attack each other. The
+// blocked squares are empty and the fixed queens are placed. The symmetry
+// reduction is not encoded.
+pub fn encode(solver: &Solver) -> String {
+    let n = solver.board_size();
+    let piece = solver.piece();
+    let var = |(i, j): (usize, usize)| variable(n, i, j) as isize;
+    let mut clauses = Vec::new();
+
+    for i in 0..n {
+        clauses.push((0..n).map(|j| var((i, j))).collect::<Vec<isize>>());
+        for j1 in 0..n {
+            for j2 in j1 + 1..n {
+                clauses.push(vec![-var((i, j1)), -var((i, j2))]);
+            }
+        }
+    }
+    // Implied by the other clauses, but it helps the SAT solvers
+    if piece.columns {
+        for j in 0..n {
+            clauses.push((0..n).map(|i| var((i, j))).collect());
+        }
+    }
+
+    let leaps = piece.leap_offsets();
+    for i in 0..n {
+        for j in 0..n {
+            // The squares of the next rows which can be attacked from (i, j),
+            // once each even if the toroidal board makes several moves reach
+            // the same square
+            let mut moves = Vec::new();
+            for i2 in i + 1..n {
+                let d = (i2 - i) as isize;
+                for &j2 in &[j as isize, j as isize - d, j as isize + d] {
+                    moves.push((i2 as isize, j2));
+                }
+            }
+            for &(di, dj) in &leaps {
+                moves.push((i as isize + di, j as isize + dj));
+            }
+            let mut targets = BTreeSet::new();
+            for (i2, j2) in moves {
+                let (i2, j2) = match solver.variant() {
+                    Variant::Toroidal => (i2.rem_euclid(n as isize), j2.rem_euclid(n as isize)),
+                    Variant::Classic => (i2, j2),
+                };
+                if i2 > i as isize && i2 < n as isize && j2 >= 0 && j2 < n as isize {
+                    targets.insert((i2 as usize, j2 as usize));
+                }
+            }
+            for target in targets {
+                if solver.attack((i, j), target) {
+                    clauses.push(vec![-var((i, j)), -var(target)]);
+                }
+            }
+        }
+    }
+
+    for square in solver.blocked_squares() {
+        clauses.push(vec![-var(square)]);
+    }
+    for queen in solver.fixed_queens() {
+        clauses.push(vec![var(queen)]);
+    }
+
+    let mut cnf = format!(
+        "c n-queens {}, variant {}, piece {}\nc variable i * {} + j + 1 is true when a queen is on row i, column j\np cnf {} {}\n",
+        n,
+        solver.variant().to_str(),
+        piece,
+        n,
+        n * n,
+        clauses.len()
+    );
+    for clause in clauses {
+        for literal in clause {
+            cnf += &format!("{} ", literal);
+        }
+        cnf += "0\n";
+    }
+    cnf
+}
+
+// The output of a SAT solver, either in the format of the SAT competitions,
+// with a status line 's SATISFIABLE' and the values on lines starting with
+// 'v', or in the format of MiniSat, with 'SAT' followed by the values.
+pub enum Model {
+    // The variables set to true
+    Satisfiable(Vec<usize>),
+    Unsatisfiable,
+}
+
+impl Model {
+    // The queens of the model, checked against the rules of the solver
+    pub fn configuration(&self, solver: &Solver) -> Result<Configuration, String> {
+        let n = solver.board_size();
+        let variables = match self {
+            Model::Satisfiable(variables) => variables,
+            Model::Unsatisfiable => return Err("the formula is unsatisfiable".to_string()),
+        };
+        let mut columns = vec![None; n];
+        for &variable in variables {
+            if variable > n * n {
+                return Err(format!(
+                    "the variable {} is not a square of the board",
+                    variable
+                ));
+            }
+            let (i, j) = ((variable - 1) / n, (variable - 1) % n);
+            if columns[i].is_some() {
+                return Err(format!("row {} holds several queens", i));
+            }
+            columns[i] = Some(j);
+        }
+        let configuration = columns
+            .iter()
+            .enumerate()
+            .map(|(i, j)| j.ok_or_else(|| format!("row {} holds no queen", i)))
+            .collect::<Result<Vec<usize>, String>>()?;
+        Ok(Configuration {
+            is_valid: solver.verify(&configuration),
+            configuration,
+            symmetry_order: None,
+            rows: None,
+        })
+    }
+}
+
+impl FromStr for Model {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut satisfiable = None;
+        let mut variables = Vec::new();
+        for line in s.lines().map(|line| line.trim()) {
+            let literals = match line.split_whitespace().next() {
+                None | Some("c") => continue,
+                Some("s") => {
+                    satisfiable = match line[1..].trim() {
+                        "SATISFIABLE" => Some(true),
+                        "UNSATISFIABLE" => Some(false),
+                        status => return Err(format!("the SAT solver answered '{}'", status)),
+                    };
+                    continue;
+                }
+                Some("SAT") => {
+                    satisfiable = Some(true);
+                    continue;
+                }
+                Some("UNSAT") => {
+                    satisfiable = Some(false);
+                    continue;
+                }
+                Some("v") => &line[1..],
+                Some(_) => line,
+            };
+            for literal in literals.split_whitespace() {
+                match literal.parse::<isize>() {
+                    Ok(literal) if literal > 0 => variables.push(literal as usize),
+                    Ok(_) => (),
+                    Err(_) => return Err(format!("invalid literal '{}'", literal)),
+                }
+            }
+        }
+        match satisfiable {
+            Some(false) => Ok(Model::Unsatisfiable),
+            Some(true) => Ok(Model::Satisfiable(variables)),
+            None if !variables.is_empty() => Ok(Model::Satisfiable(variables)),
+            None => Err("no model found".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::Piece;
+
+    fn clauses(cnf: &str) -> Vec<Vec<isize>> {
+        cnf.lines()
+            .filter(|line| !line.starts_with('c') && !line.starts_with('p'))
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|literal| literal.parse().unwrap())
+                    .take_while(|&literal| literal != 0)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn small_boards_have_the_expected_clauses() {
+        for n in 1..=6 {
+            let pairs = n * (n - 1) / 2;
+            // On each diagonal, every pair of squares attack each other
+            let diagonals = 2 * ((1..n).map(|l| l * (l - 1)).sum::<usize>() + pairs);
+            let expected = n + n * pairs + n + n * pairs + diagonals;
+            let cnf = encode(&Solver::new(n));
+            assert!(cnf.contains(&format!("p cnf {} {}\n", n * n, expected)));
+            assert_eq!(clauses(&cnf).len(), expected);
+        }
+    }
+
+    #[test]
+    fn clauses_hold_for_the_solutions_only() {
+        let solvers = [
+            Solver::new(5),
+            Solver::new(5).with_variant(Variant::Toroidal),
+            Solver::new(5).with_piece("QN".parse::<Piece>().unwrap()),
+            Solver::new(4)
+                .with_piece(Piece::bishop())
+                .with_blocked_squares(&[(1, 1)])
+                .unwrap(),
+            Solver::new(5).with_fixed_queens(&[(2, 0)]).unwrap(),
+        ];
+        for solver in &solvers {
+            let n = solver.board_size();
+            let clauses = clauses(&encode(solver));
+            for k in 0..n.pow(n as u32) {
+                let configuration = (0..n)
+                    .map(|i| k / n.pow(i as u32) % n)
+                    .collect::<Vec<usize>>();
+                let holds = |literal: isize| {
+                    let (i, j) = (
+                        (literal.unsigned_abs() - 1) / n,
+                        (literal.unsigned_abs() - 1) % n,
+                    );
+                    (configuration[i] == j) == (literal > 0)
+                };
+                let satisfied = clauses
+                    .iter()
+                    .all(|clause| clause.iter().any(|&literal| holds(literal)));
+                assert_eq!(satisfied, solver.verify(&configuration));
+            }
+        }
+    }
+
+    #[test]
+    fn models_are_read_from_the_sat_solvers() {
+        let solver = Solver::new(4);
+        // The solution 1, 3, 0, 2
+        let accepted = [
+            "c comment\ns SATISFIABLE\nv -1 2 -3 -4 -5 -6 -7 8\nv 9 -10 -11 -12 -13 -14 15 -16 0\n",
+            "SAT\n-1 2 -3 -4 -5 -6 -7 8 9 -10 -11 -12 -13 -14 15 -16 0\n",
+            "2 8 9 15 0",
+        ];
+        for output in &accepted {
+            let model = output.parse::<Model>().unwrap();
+            let configuration = model.configuration(&solver).unwrap();
+            assert_eq!(configuration.configuration, vec![1, 3, 0, 2]);
+            assert!(configuration.is_valid);
+        }
+        for output in &["s UNSATISFIABLE\n", "UNSAT\n"] {
+            let model = output.parse::<Model>().unwrap();
+            assert!(matches!(model, Model::Unsatisfiable));
+            assert!(model.configuration(&solver).is_err());
+        }
+
+        let rejected = ["", "c nothing\n", "s UNKNOWN\n", "s SATISFIABLE\nv 1 x 0\n"];
+        for output in &rejected {
+            assert!(output.parse::<Model>().is_err(), "{}", output);
+        }
+        // Too many, too few or out of the board
+        for output in &["1 2 8 9 15 0", "2 8 9 0", "2 8 9 17 0"] {
+            let model = output.parse::<Model>().unwrap();
+            assert!(model.configuration(&solver).is_err(), "{}", output);
+        }
+        // Queens attacking each other are read, but not valid
+        let model = "1 6 11 16 0".parse::<Model>().unwrap();
+        assert!(!model.configuration(&solver).unwrap().is_valid);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::solver::{SearchEvent, Solver};
+
+// Beyond this, the drawing is unreadable anyway
+const MAX_NODES: usize = 100_000;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Outcome {
+    // The node has children
+    Explored,
+    Solution,
+    // No queen fits on the line filled next
+    DeadEnd,
+    // The square cannot hold a queen, or the placement is cut by the symmetry
+    // reduction or the range of the search
+    Pruned,
+}
+
+struct Node {
+    queens: Vec<(usize, usize)>,
+    parent: Option<usize>,
+    outcome: Option<Outcome>,
+}
+
+// A node being explored, with the squares of its children
+struct Frame {
+    node: usize,
+    children: Vec<(usize, usize)>,
+}
+
+// Runs the search to the end and draws the tree it explored in the DOT format
+// of Graphviz. Each node is labeled with its queens, as the column of each
+// row, or as (row, column) when they are not on the first rows. The solutions
+// are green, the dead ends red, and the squares which were not tried, since
+// they cannot hold a queen, are grey.
+// Returns an error if the tree is too large to be drawn.
+pub fn search_tree(solver: &mut Solver) -> Result<String, String> {
+    let n = solver.board_size();
+    let mut nodes = vec![Node {
+        queens: solver.queens(),
+        parent: None,
+        outcome: None,
+    }];
+    let mut stack = vec![Frame {
+        node: 0,
+        children: Vec::new(),
+    }];
+
+    loop {
+        if nodes.len() > MAX_NODES {
+            return Err(format!(
+                "the search tree has more than {} nodes, too many to be drawn",
+                MAX_NODES
+            ));
+        }
+        let event = solver.next_event();
+        let frame = match stack.last_mut() {
+            Some(frame) => frame,
+            None => break,
+        };
+        let parent = frame.node;
+        // The squares of the line which were not tried from the node
+        let pruned = match event {
+            SearchEvent::Place(i, j) => {
+                frame.children.push((i, j));
+                let mut queens = nodes[parent].queens.clone();
+                queens.push((i, j));
+                nodes[parent].outcome = Some(Outcome::Explored);
+                nodes.push(Node {
+                    queens,
+                    parent: Some(parent),
+                    outcome: None,
+                });
+                stack.push(Frame {
+                    node: nodes.len() - 1,
+                    children: Vec::new(),
+                });
+                continue;
+            }
+            SearchEvent::Remove(_, _) => {
+                let node = &mut nodes[parent];
+                // A complete placement which is not a solution was cut by the
+                // symmetry reduction
+                node.outcome.get_or_insert(Outcome::Pruned);
+                stack.pop();
+                continue;
+            }
+            SearchEvent::Solution => {
+                nodes[parent].outcome = Some(Outcome::Solution);
+                continue;
+            }
+            SearchEvent::DeadEnd(i) => (0..n).map(|j| (i, j)).collect::<Vec<_>>(),
+            SearchEvent::ColumnDeadEnd(j) => (0..n).map(|i| (i, j)).collect(),
+            SearchEvent::Exhausted => break,
+        };
+        let pruned = pruned
+            .into_iter()
+            .filter(|square| !frame.children.contains(square))
+            .collect::<Vec<_>>();
+        if frame.children.is_empty() {
+            nodes[parent].outcome = Some(Outcome::DeadEnd);
+        }
+        // The squares on the rows already filled were never candidates
+        for square in pruned {
+            if nodes[parent].queens.iter().any(|&(i, _)| i == square.0) {
+                continue;
+            }
+            let mut queens = nodes[parent].queens.clone();
+            queens.push(square);
+            nodes.push(Node {
+                queens,
+                parent: Some(parent),
+                outcome: Some(Outcome::Pruned),
+            });
+        }
+    }
+
+    // The children of each node are written in the order of their squares,
+    // so that the columns are drawn from left to right
+    let mut children = vec![Vec::new(); nodes.len()];
+    for (k, node) in nodes.iter().enumerate() {
+        if let Some(parent) = node.parent {
+            children[parent].push(k);
+        }
+    }
+    let mut order = Vec::new();
+    let mut pending = vec![0];
+    while let Some(k) = pending.pop() {
+        order.push(k);
+        children[k].sort_by_key(|&child| nodes[child].queens.last().copied());
+        pending.extend(children[k].iter().rev());
+    }
+
+    let mut dot = String::from(
+        "digraph search {\n    node [shape=box, style=filled, fillcolor=white, fontname=\"monospace\"];\n",
+    );
+    for k in order {
+        let node = &nodes[k];
+        let style = match node.outcome {
+            Some(Outcome::Solution) => ", fillcolor=palegreen",
+            Some(Outcome::DeadEnd) => ", fillcolor=salmon",
+            Some(Outcome::Pruned) => {
+                ", fillcolor=lightgrey, fontcolor=grey40, style=\"filled,dashed\""
+            }
+            Some(Outcome::Explored) | None => "",
+        };
+        dot += &format!("    {} [label=\"{}\"{}];\n", k, label(&node.queens), style);
+        if let Some(parent) = node.parent {
+            dot += &format!("    {} -> {};\n", parent, k);
+        }
+    }
+    dot += "}\n";
+    Ok(dot)
+}
+
+fn label(queens: &[(usize, usize)]) -> String {
+    if queens.iter().enumerate().all(|(k, &(i, _))| i == k) {
+        format!(
+            "{:?}",
+            queens.iter().map(|&(_, j)| j).collect::<Vec<usize>>()
+        )
+    } else {
+        format!("{:?}", queens)
+    }
+}
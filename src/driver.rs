@@ -10,6 +10,12 @@ pub enum StrategySelection {
     OnlyCount,
 }
 
+pub enum Algorithm {
+    Backtracking,
+    MinConflicts,
+    Construct,
+}
+
 pub enum InteractionMode {
     NoInteraction,
     WaitUser,
@@ -41,6 +47,29 @@ impl FromStr for StrategySelection {
     }
 }
 
+impl Algorithm {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Algorithm::Backtracking => "backtracking",
+            Algorithm::MinConflicts => "min-conflicts",
+            Algorithm::Construct => "construct",
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "backtracking" => Ok(Algorithm::Backtracking),
+            "min-conflicts" => Ok(Algorithm::MinConflicts),
+            "construct" => Ok(Algorithm::Construct),
+            _ => Err("no match"),
+        }
+    }
+}
+
 impl InteractionMode {
     pub fn to_str(&self) -> &'static str {
         match self {
@@ -68,5 +97,7 @@ pub trait Driver<T>
 where
     T: Strategy,
 {
-    fn execute(&mut self, solver: Solver, strategy: T, interaction_mode: InteractionMode);
+    // Returns the solver once the search is over, or stopped by the user
+    fn execute(&mut self, solver: Solver, strategy: T, interaction_mode: InteractionMode)
+        -> Solver;
 }
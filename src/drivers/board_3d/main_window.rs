@@ -3,6 +3,7 @@ use std::path::Path;
 use std::rc::Rc;
 
 use crate::driver::*;
+use crate::ranking::Ranking;
 use crate::solver::Solver;
 use crate::strategy::Strategy;
 
@@ -23,8 +24,14 @@ pub struct Driver3D {
     font: Rc<Font>,
     queens: Vec<SceneNode>,
     arc_ball: ArcBall,
+    // Jumps between the solutions, when they are searched in lexicographic
+    // order
+    ranking: Option<Ranking>,
 }
 
+// Number of solutions skipped at once when paging
+const PAGE: u64 = 1000;
+
 impl Driver3D {
     pub fn new(board_size: usize) -> Self {
         let mut window = Window::new_with_size("N-Queen solver", 720, 720);
@@ -46,7 +53,7 @@ impl Driver3D {
         let queen_mtl = Path::new("resources/queen.mtl");
 
         let mut manager = MeshManager::new();
-        MeshManager::load_obj(&queen_obj, &queen_mtl, "queen")
+        MeshManager::load_obj(queen_obj, queen_mtl, "queen")
             .unwrap()
             .into_iter()
             .for_each(|(name, mesh, _)| {
@@ -79,12 +86,35 @@ impl Driver3D {
             font: Font::default(),
             queens,
             arc_ball,
+            ranking: None,
+        }
+    }
+
+    // Allows to page through the solutions with the counts of `ranking`, if
+    // given
+    pub fn with_paging(mut self, ranking: Option<Ranking>) -> Self {
+        self.ranking = ranking;
+        self
+    }
+
+    // Moves the queens of the empty rows off the board
+    fn place_queens(&mut self, queens: &[(usize, usize)]) {
+        let mut positions = vec![-1.; self.board_size];
+        for &(i, j) in queens {
+            positions[i] = j as f32;
+        }
+        let it = self.queens.iter_mut().zip(positions);
+        for (i, (queen, pos)) in it.enumerate() {
+            queen.set_local_translation(Translation3::new(pos, 0.0, i as f32));
         }
     }
 }
 
 const USAGE: &str = r#"Usage:
 Press <Space> to advance one step or maintain to advance quickly
+Press <F> to explore what follows the current queens apart
+Press <Backspace> to leave it and resume the search
+Press <PageDown> or <PageUp> to skip 1000 solutions forward or backward
 Press <Enter> to go back at the origin
 Press <Esc> to quit
 Mouse left click to rotate
@@ -94,16 +124,68 @@ Mouse wheel to zoom
 
 impl<T> Driver<T> for Driver3D
 where
-    T: Strategy,
+    T: Strategy + Clone,
 {
-    fn execute(&mut self, solver: Solver, strategy: T, interaction_mode: InteractionMode) {
+    fn execute(
+        &mut self,
+        solver: Solver,
+        strategy: T,
+        interaction_mode: InteractionMode,
+    ) -> Solver {
         let mut solver = solver;
         let mut strategy = strategy;
+        // Given again to the search after a jump
+        let initial_strategy = strategy.clone();
         let mut end = false;
+        // The searches left for a fork, to be resumed in reverse order
+        let mut forks: Vec<(Solver, T, bool)> = Vec::new();
 
         self.window.set_light(Light::StickToCamera);
 
         while !self.window.should_close() {
+            let (mut advance, mut fork, mut back) = (false, false, false);
+            let mut jump = None;
+            for event in self.window.events().iter() {
+                match event.value {
+                    WindowEvent::Key(Key::Space, Action::Press, _) => advance = true,
+                    WindowEvent::Key(Key::F, Action::Press, _) => fork = true,
+                    WindowEvent::Key(Key::Back, Action::Press, _) => back = true,
+                    WindowEvent::Key(Key::PageDown, Action::Press, _) => {
+                        jump = Some(solver.solutions_found() + PAGE)
+                    }
+                    WindowEvent::Key(Key::PageUp, Action::Press, _) => {
+                        jump = Some(solver.solutions_found().saturating_sub(PAGE))
+                    }
+                    _ => (),
+                }
+            }
+            // The next solution is the one at the index, if there is one. The
+            // forks do not follow the lexicographic order of the whole search.
+            let jump = jump.filter(|_| forks.is_empty());
+            if let (Some(index), Some(ranking)) = (jump, &mut self.ranking) {
+                if let Some(positioned) = ranking.solver_at(index) {
+                    solver = positioned;
+                    strategy = initial_strategy.clone();
+                    end = false;
+                    self.place_queens(&solver.queens());
+                }
+            }
+            if fork && !end {
+                let forked = solver.fork();
+                forks.push((
+                    std::mem::replace(&mut solver, forked),
+                    strategy.clone(),
+                    end,
+                ));
+            } else if back {
+                if let Some((previous, previous_strategy, previous_end)) = forks.pop() {
+                    solver = previous;
+                    strategy = previous_strategy;
+                    end = previous_end;
+                    self.place_queens(&solver.queens());
+                }
+            }
+
             self.window.draw_text(
                 USAGE,
                 &Point2::origin(),
@@ -112,9 +194,20 @@ where
                 &Point3::new(1.0, 1.0, 1.0),
             );
 
+            let mut found = if solver.reduces_symmetries() {
+                format!(
+                    "Fundamental solutions found so far: {}",
+                    solver.solutions_found()
+                )
+            } else {
+                format!("Solutions found so far: {}", solver.solutions_found())
+            };
+            if !forks.is_empty() {
+                found += &format!(" (fork {})", forks.len());
+            }
             self.window.draw_text(
-                &format!("Solutions found so far: {}", solver.solutions_found())[..],
-                &Point2::new(0.0, 220.0),
+                &found[..],
+                &Point2::new(0.0, 290.0),
                 60.0,
                 &self.font,
                 &Point3::new(1.0, 1.0, 0.0),
@@ -123,25 +216,11 @@ where
             if !end {
                 let step = strategy.next_step(&mut solver);
                 if let Some(config) = step {
-                    let mut positions = config
-                        .configuration
-                        .into_iter()
-                        .map(|x| x as f32)
-                        .collect::<Vec<f32>>();
-                    positions.resize(self.board_size, -1.);
-                    let it = self.queens.iter_mut().zip(positions.into_iter());
-                    for (i, (queen, pos)) in it.enumerate() {
-                        queen.set_local_translation(Translation3::new(pos, 0.0, i as f32));
-                    }
+                    self.place_queens(&config.queens());
                 } else {
                     if let InteractionMode::WaitUser = interaction_mode {
-                        for event in self.window.events().iter() {
-                            match event.value {
-                                WindowEvent::Key(Key::Space, Action::Press, _) => {
-                                    end = !strategy.has_next_batch(&mut solver);
-                                }
-                                _ => {}
-                            }
+                        if advance {
+                            end = !strategy.has_next_batch(&mut solver);
                         }
                     } else {
                         end = !strategy.has_next_batch(&mut solver);
@@ -150,14 +229,28 @@ where
             } else {
                 self.window.draw_text(
                     "No other partial solution exists",
-                    &Point2::new(0.0, 280.0),
+                    &Point2::new(0.0, 350.0),
                     60.0,
                     &self.font,
                     &Point3::new(1.0, 0.0, 0.0),
                 );
+                if let Some(statistics) = solver.statistics() {
+                    self.window.draw_text(
+                        &statistics.to_string(),
+                        &Point2::new(0.0, 410.0),
+                        40.0,
+                        &self.font,
+                        &Point3::new(1.0, 1.0, 1.0),
+                    );
+                }
             }
 
             self.window.render_with_camera(&mut self.arc_ball);
         }
+        // The forks are left aside
+        match forks.into_iter().next() {
+            Some((original, _, _)) => original,
+            None => solver,
+        }
     }
 }
@@ -1,7 +1,7 @@
 use std::io::Write;
 
 use crate::driver::*;
-use crate::solver::Solver;
+use crate::solver::{Configuration, Solver};
 use crate::strategy::Strategy;
 
 pub struct ConsoleDriver {
@@ -12,13 +12,32 @@ impl ConsoleDriver {
     pub fn new(board_size: usize) -> Self {
         Self { board_size }
     }
+
+    pub fn display(&self, config: &Configuration) {
+        match &config.rows {
+            Some(_) => print!("{:?}", config.queens()),
+            None => print!("{:?}", config.configuration),
+        }
+        if config.is_valid {
+            print!("\nSolution found!");
+            if let Some(order) = config.symmetry_order {
+                print!(" Symmetry order: {}", order);
+            }
+        }
+        println!()
+    }
 }
 
 impl<T> Driver<T> for ConsoleDriver
 where
     T: Strategy,
 {
-    fn execute(&mut self, solver: Solver, strategy: T, interaction_mode: InteractionMode) {
+    fn execute(
+        &mut self,
+        solver: Solver,
+        strategy: T,
+        interaction_mode: InteractionMode,
+    ) -> Solver {
         let interaction = match interaction_mode {
             InteractionMode::NoInteraction => || {
                 std::io::stdout().flush().unwrap();
@@ -37,11 +56,7 @@ where
         let mut strategy = strategy;
         while strategy.has_next_batch(&mut solver) {
             while let Some(config) = strategy.next_step(&mut solver) {
-                print!("{:?}", config.configuration);
-                if config.is_valid {
-                    print!("\nSolution found!");
-                }
-                println!()
+                self.display(&config);
             }
             print!("\x1BA");
             interaction();
@@ -49,9 +64,19 @@ where
 
         if solver.solutions_found() == 0 {
             println!("No solution!");
+        } else if solver.reduces_symmetries() {
+            println!(
+                "Number of fundamental solutions found: {}",
+                solver.solutions_found()
+            );
+            println!("Number of solutions found: {}", solver.total_solutions());
         } else {
             println!("Number of solutions found: {}", solver.solutions_found());
         }
+        if let Some(statistics) = solver.statistics() {
+            println!("{}", statistics);
+        }
         std::io::stdout().flush().unwrap();
+        solver
     }
 }
@@ -1,8 +1,22 @@
 pub use self::drivers::*;
 pub use self::strategies::*;
 
+pub mod board;
+pub mod completions;
+pub mod construct;
+pub mod dancing_links;
+pub mod dimacs;
+pub mod dot;
 pub mod driver;
 pub mod drivers;
+pub mod min_conflicts;
+pub mod parallel;
+pub mod piece;
+pub mod random;
+pub mod ranking;
+pub mod selftest;
 pub mod solver;
+pub mod statistics;
 pub mod strategies;
 pub mod strategy;
+pub mod symmetry;
@@ -1,15 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use n_queens::board::Board;
+use n_queens::completions::Completions;
+use n_queens::construct;
+use n_queens::dimacs::{self, Model};
+use n_queens::dot;
 use n_queens::driver::*;
 use n_queens::drivers::*;
-use n_queens::solver::Solver;
+use n_queens::min_conflicts;
+use n_queens::piece::Piece;
+use n_queens::ranking::Ranking;
+use n_queens::selftest;
+use n_queens::solver::{
+    Backend, RowOrder, Solver, SolverError, SolverOptions, ValueOrder, Variant,
+};
 use n_queens::strategies::*;
 use n_queens::strategy::*;
 
 #[macro_use]
 extern crate clap;
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
 
 const DEFAULT_BOARD_SIZE: usize = 7;
 
+// Stops the program if one of the `options` is given, since `what` cannot be
+// used with them
+fn reject_options(matches: &ArgMatches, options: &[&str], what: &str) {
+    if let Some(option) = options.iter().find(|&&option| matches.is_present(option)) {
+        eprintln!("{} cannot be used with --{}.", what, option);
+        std::process::exit(1);
+    }
+}
+
+// Exit code for each invalid combination of options, 1 being used for the
+// other errors
+fn exit_code(error: &SolverError) -> i32 {
+    match error {
+        SolverError::ZeroSize => 2,
+        SolverError::TooLarge(_, _) => 3,
+        SolverError::AsymmetricPiece(_) | SolverError::BrokenSymmetry => 4,
+        SolverError::InvalidPlacement(_) => 5,
+    }
+}
+
+fn parse_queens(queens: &str) -> Option<Vec<(usize, usize)>> {
+    queens
+        .split(',')
+        .map(|queen| {
+            let mut coordinates = queen.trim().splitn(2, ':').map(|x| x.parse::<usize>().ok());
+            Some((coordinates.next()??, coordinates.next()??))
+        })
+        .collect()
+}
+
+fn parse_solution(solution: &str) -> Option<Vec<usize>> {
+    solution
+        .split(',')
+        .map(|column| column.trim().parse::<usize>().ok())
+        .collect()
+}
+
+fn parse_range(range: &str) -> Option<(Vec<usize>, Vec<usize>)> {
+    let mut bounds = range.splitn(2, ':').map(|bound| match bound.trim() {
+        "" => Some(Vec::new()),
+        bound => parse_solution(bound),
+    });
+    Some((bounds.next()??, bounds.next()??))
+}
+
+fn format_range(start: &[usize], end: &[usize]) -> String {
+    let columns = |bound: &[usize]| {
+        bound
+            .iter()
+            .map(|j| j.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+    format!("{}:{}", columns(start), columns(end))
+}
+
 fn main() {
     let matches = App::new("N-Queens solver")
         .version("0.3.14.15...")
@@ -20,6 +90,62 @@ fn main() {
 				.index(1)
 				.help(&format!("The size of the board, and so the number of queens. Default is {}", DEFAULT_BOARD_SIZE)[..])
 		)
+        .arg(
+            Arg::with_name("algorithm")
+                .long("-algorithm")
+                .value_name("Algorithm")
+                .possible_values(
+                    &[Algorithm::Backtracking, Algorithm::MinConflicts, Algorithm::Construct]
+					.iter()
+					.map(|x| x.to_str())
+                    .collect::<Vec<&str>>()[..],
+				)
+                .help(&format!("Select the algorithm.\n'{}' explores all the placements, with the chosen strategy and driver.\n'{}' is a local search finding a single solution, even for very large boards.\n'{}' builds a single solution from a formula, without any search.\nThe last two only support the classic rules with queens, and write the solution in the console.\nDefault is '{}'.\n", Algorithm::Backtracking.to_str(), Algorithm::MinConflicts.to_str(), Algorithm::Construct.to_str(), Algorithm::Backtracking.to_str())[..]),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("-seed")
+                .value_name("Seed")
+                .conflicts_with_all(&["resume", "checkpoint"])
+                .help("Seed of the random choices. With the backtracking algorithm, the columns of each row are tried in a random order, unless another order is given with --values. With the min-conflicts algorithm, the queens start on random columns. The same seed always gives the same search.\nDefault is 0, and the backtracking algorithm tries the columns in order.\n"),
+        )
+        .arg(
+            Arg::with_name("values")
+                .long("-values")
+                .value_name("ValueOrder")
+                .conflicts_with_all(&["resume", "checkpoint", "samples"])
+                .possible_values(
+                    &[
+                        ValueOrder::LeftToRight,
+                        ValueOrder::MiddleOut,
+                        ValueOrder::Random(0),
+                        ValueOrder::LeastConstraining,
+                    ]
+					.iter()
+					.map(|x| x.to_str())
+                    .collect::<Vec<&str>>()[..],
+				)
+                .help(&format!("Select the order in which the columns of each row are tried. It does not change the solutions found, only the order in which they are found.\n'{}' tries the columns from the center of the row to its edges.\n'{}' shuffles them with the seed.\n'{}' tries first the columns leaving the most free squares on the other rows.\nDefault is '{}'.\n", ValueOrder::MiddleOut.to_str(), ValueOrder::Random(0).to_str(), ValueOrder::LeastConstraining.to_str(), ValueOrder::LeftToRight.to_str())[..]),
+        )
+        .arg(
+            Arg::with_name("rows")
+                .long("-rows")
+                .value_name("RowOrder")
+                .conflicts_with_all(&["resume", "checkpoint"])
+                .possible_values(
+                    &[RowOrder::TopDown, RowOrder::MostConstrained]
+					.iter()
+					.map(|x| x.to_str())
+                    .collect::<Vec<&str>>()[..],
+				)
+                .help(&format!("Select the order in which the rows are filled. It does not change the solutions found, only the order in which they are found.\n'{}' fills first the row with the fewest free columns.\nDefault is '{}'.\n", RowOrder::MostConstrained.to_str(), RowOrder::TopDown.to_str())[..]),
+        )
+        .arg(
+            Arg::with_name("samples")
+                .long("-samples")
+                .value_name("Count")
+                .help("Write this number of distinct solutions drawn at random in the console, using the seed, instead of exploring the placements. Only used with the backtracking algorithm."),
+        )
         .arg(
             Arg::with_name("driver")
                 .long("-driver")
@@ -34,7 +160,7 @@ fn main() {
                 .long("-strat")
                 .value_name("Strategy")
                 .possible_values(
-                    &vec![
+                    &[
                         StrategySelection::AllSteps,
 						StrategySelection::SolutionsWithSteps,
 						StrategySelection::OnlySolutions,
@@ -51,7 +177,7 @@ fn main() {
                 .long("-interaction")
                 .value_name("InterationMode")
                 .possible_values(
-                    &vec![
+                    &[
                         InteractionMode::NoInteraction,
                         InteractionMode::WaitUser,
                         InteractionMode::WaitOrTimeout(0.),
@@ -62,18 +188,774 @@ fn main() {
 				)
                 .help(&format!("Select the Interaction Mode. WaitOrTimeout is not yet implemented.\nDefault is '{}'.\n", InteractionMode::WaitUser.to_str())[..]),
         )
+        .arg(
+            Arg::with_name("backend")
+                .long("-backend")
+                .value_name("Backend")
+                .possible_values(
+                    &[Backend::Vectors, Backend::Bitboard, Backend::DancingLinks]
+					.iter()
+					.map(|x| x.to_str())
+                    .collect::<Vec<&str>>()[..],
+				)
+                .help(&format!("Select how the solver keeps track of the attacked lines.\n'{}' uses vectors of booleans.\n'{}' uses bit masks and is faster, but only supports boards up to {}.\n'{}' solves an exact cover problem with dancing links, filling each time the row or the column with the fewest free squares, on boards up to {}.\nDefault is '{}' when the board fits, '{}' otherwise.\n", Backend::Vectors.to_str(), Backend::Bitboard.to_str(), Backend::Bitboard.max_board_size(), Backend::DancingLinks.to_str(), Backend::DancingLinks.max_board_size(), Backend::Bitboard.to_str(), Backend::Vectors.to_str())[..]),
+        )
+        .arg(
+            Arg::with_name("threads")
+                .long("-threads")
+                .value_name("Threads")
+                .help("Number of threads used to count the solutions. Only used if the strategy is set to OnlyCount.\nDefault is 1.\n"),
+        )
+        .arg(
+            Arg::with_name("fundamental")
+                .long("-fundamental")
+                .help("If enabled, only one solution of each equivalence class under the symmetries of the square is shown and counted, along with its symmetry order."),
+        )
+        .arg(
+            Arg::with_name("checkpoint")
+                .long("-checkpoint")
+                .value_name("File")
+                .help("Regularly save the state of the search in this file, so that it can be resumed later with --resume. Only used if the strategy is set to OnlyCount, and cannot be used with several threads."),
+        )
+        .arg(
+            Arg::with_name("resume")
+                .long("-resume")
+                .value_name("File")
+                .conflicts_with_all(&["size", "backend", "fundamental"])
+                .help("Resume the search saved in this file with --checkpoint. The size of the board and the options of the solver are taken from the file."),
+        )
+        .arg(
+            Arg::with_name("fixed")
+                .long("-fixed")
+                .value_name("Queens")
+                .conflicts_with_all(&["fundamental", "resume"])
+                .help("Queens already placed on the board, given as a list of row:column separated by commas, for instance '0:3,5:1'. Only the placements completing them are explored."),
+        )
+        .arg(
+            Arg::with_name("board")
+                .long("-board")
+                .value_name("File")
+                .conflicts_with_all(&["size", "fundamental", "resume"])
+                .help("Read the board from this file, one line per row: '.' is a free square and '#' a blocked square. No queen can be placed on a blocked square, but the attacks pass through it. The size of the board is given by the file."),
+        )
+        .arg(
+            Arg::with_name("variant")
+                .long("-variant")
+                .value_name("Variant")
+                .case_insensitive(true)
+                .conflicts_with("resume")
+                .possible_values(
+                    &[Variant::Classic, Variant::Toroidal]
+					.iter()
+					.map(|x| x.to_str())
+                    .collect::<Vec<&str>>()[..],
+				)
+                .help(&format!("Select the rules of the board.\n'{}' is the usual board.\n'{}' wraps the diagonals around the edges of the board. It only has solutions when the size is coprime with 6.\nDefault is '{}'.\n", Variant::Classic.to_str(), Variant::Toroidal.to_str(), Variant::Classic.to_str())[..]),
+        )
+        .arg(
+            Arg::with_name("piece")
+                .long("-piece")
+                .value_name("Piece")
+                .conflicts_with("resume")
+                .help("Select the piece placed on the board, one per row: 'Queen', 'Rook', 'Bishop', 'Superqueen' or 'Amazon' (a queen which also moves as a knight). Other pieces can be given in Betza notation: 'Q', 'R' or 'B' for the lines, followed by the leapers 'W', 'F', 'D', 'N', 'A', 'H', 'C', 'Z', 'G', or '(a,b)' for any other leap. For instance, 'BN' is a bishop which also moves as a knight.\nDefault is 'Queen'.\n"),
+        )
+        .arg(
+            Arg::with_name("nth")
+                .long("-nth")
+                .value_name("Index")
+                .conflicts_with_all(&["resume", "seed", "samples", "values", "rows"])
+                .help("Start the search at the solution with this index in lexicographic order, counting from 0. The solutions before it are counted without being explored one by one."),
+        )
+        .arg(
+            Arg::with_name("range")
+                .long("-range")
+                .value_name("Range")
+                .conflicts_with_all(&["resume", "seed", "samples", "nth", "values", "rows"])
+                .help("Only explore the placements whose first rows lie between two bounds in lexicographic order, both included. The bounds give the columns of the first rows, and are separated by a colon, for instance '3,0:5,7'. A bound can be left empty."),
+        )
         .arg(
 			Arg::with_name("stop-after-first")
                 .long("-stop-after-first")
                 .help("If enabled, the solver will not output any more steps as soon as a solution is found. Has no effect if the strategy is set to OnlyCount (it will still count all solutions)."),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .long("-stats")
+                .conflicts_with("samples")
+                .help("Collect statistics on the search, written by the driver at the end: the nodes visited, the queens placed, the backtracks, the dead ends of each row, the maximum depth and the elapsed time."),
+        )
+        .arg(
+            Arg::with_name("stats-file")
+                .long("-stats-file")
+                .value_name("File")
+                .conflicts_with("samples")
+                .help("Write the statistics of the search in this file at the end, as a JSON object."),
+        )
+        .subcommand(
+            SubCommand::with_name("rank")
+                .about("Gives the index of a solution in lexicographic order, counting from 0")
+                .arg(
+                    Arg::with_name("solution")
+                        .index(1)
+                        .required(true)
+                        .help("The column of the queen of each row, separated by commas, for instance '0,4,7,5,2,6,1,3'. The size of the board is given by the solution."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("split")
+                .about("Splits the search into ranges with about the same number of placements of the first queens, to be given to --range")
+                .arg(
+                    Arg::with_name("parts")
+                        .index(1)
+                        .required(true)
+                        .help("The number of ranges. Less ranges are given if the search is too small."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dimacs")
+                .about("Writes the rules of the board as a CNF formula in the DIMACS format, for a SAT solver")
+                .arg(
+                    Arg::with_name("file")
+                        .index(1)
+                        .help("The file written. The formula is written in the console if no file is given."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("model")
+                .about("Reads the model found by a SAT solver for the formula written by the dimacs command, and checks it")
+                .arg(
+                    Arg::with_name("file")
+                        .index(1)
+                        .required(true)
+                        .help("The output of the SAT solver. The other options must be the same as for the dimacs command."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("tree")
+                .about("Draws the whole tree explored by the search in the DOT format of Graphviz, for small boards")
+                .arg(
+                    Arg::with_name("file")
+                        .index(1)
+                        .help("The file written. The tree is written in the console if no file is given."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("selftest")
+                .about("Counts the solutions of each size from 1, with and without the symmetry reduction, and checks them against the known numbers")
+                .arg(
+                    Arg::with_name("size")
+                        .index(1)
+                        .help("The largest size checked, up to 27. The backend, the threads and the orders given by the other options are used.\nDefault is 12.\n"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("complete")
+                .about("Counts the solutions extending queens placed on the first rows, and how they split over the columns of the next row")
+                .arg(
+                    Arg::with_name("prefix")
+                        .index(1)
+                        .help("The column of the queen of each of the first rows, separated by commas, for instance '0,4'. The solutions of the whole board are counted if no prefix is given."),
+                ),
         )
 		.get_matches();
 
-    let board_size = value_t!(matches, "size", usize).unwrap_or(DEFAULT_BOARD_SIZE);
-    let solver = Solver::new(board_size);
+    let algorithm = value_t!(matches, "algorithm", Algorithm).unwrap_or(Algorithm::Backtracking);
+    if let Algorithm::MinConflicts | Algorithm::Construct = algorithm {
+        let unsupported = [
+            "resume",
+            "board",
+            "fixed",
+            "fundamental",
+            "variant",
+            "piece",
+            "samples",
+            "nth",
+            "range",
+            "values",
+            "rows",
+            "stats",
+            "stats-file",
+        ];
+        reject_options(
+            &matches,
+            &unsupported,
+            &format!("The {} algorithm", algorithm.to_str()),
+        );
+        if matches.value_of("driver") == Some("3D") {
+            eprintln!(
+                "The {} algorithm only writes in the console.",
+                algorithm.to_str()
+            );
+            std::process::exit(1);
+        }
+        if let Some(command) = matches.subcommand_name() {
+            eprintln!(
+                "The {} algorithm cannot be used with the {} command.",
+                algorithm.to_str(),
+                command
+            );
+            std::process::exit(1);
+        }
+    }
+    if matches!(algorithm, Algorithm::Backtracking)
+        && matches.value_of("backend") == Some(Backend::DancingLinks.to_str())
+    {
+        let unsupported = [
+            "checkpoint",
+            "seed",
+            "samples",
+            "nth",
+            "range",
+            "values",
+            "rows",
+        ];
+        reject_options(
+            &matches,
+            &unsupported,
+            &format!("The {} backend", Backend::DancingLinks.to_str()),
+        );
+        if matches.subcommand_name() == Some("split") {
+            eprintln!(
+                "The {} backend cannot be used with the split command.",
+                Backend::DancingLinks.to_str()
+            );
+            std::process::exit(1);
+        }
+    }
+    let seed = if matches.is_present("seed") {
+        match value_t!(matches, "seed", u64) {
+            Ok(seed) => seed,
+            Err(_) => {
+                eprintln!("The seed must be a non-negative integer.");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        0
+    };
+    let samples = if matches.is_present("samples") {
+        match value_t!(matches, "samples", usize) {
+            Ok(samples) => Some(samples),
+            Err(_) => {
+                eprintln!("The number of samples must be a non-negative integer.");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    let nth = if matches.is_present("nth") {
+        match value_t!(matches, "nth", u64) {
+            Ok(nth) => Some(nth),
+            Err(_) => {
+                eprintln!("The index of the solution must be a non-negative integer.");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    let rank_solution = matches.subcommand_matches("rank").map(|rank| {
+        let solution = rank.value_of("solution").unwrap();
+        match parse_solution(solution) {
+            Some(solution) => solution,
+            None => {
+                eprintln!(
+                    "Invalid solution '{}', expected for instance '0,4,7,5,2,6,1,3'.",
+                    solution
+                );
+                std::process::exit(1);
+            }
+        }
+    });
+    let range = matches
+        .value_of("range")
+        .map(|range| match parse_range(range) {
+            Some(range) => range,
+            None => {
+                eprintln!(
+                    "Invalid range '{}', expected for instance '3,0:5,7'.",
+                    range
+                );
+                std::process::exit(1);
+            }
+        });
+    let split_parts =
+        matches
+            .subcommand_matches("split")
+            .map(|split| match value_t!(split, "parts", usize) {
+                Ok(parts) if parts > 0 => parts,
+                _ => {
+                    eprintln!("The number of ranges must be a positive integer.");
+                    std::process::exit(1);
+                }
+            });
+    if split_parts.is_some() && matches.is_present("resume") {
+        eprintln!("A resumed search cannot be split.");
+        std::process::exit(1);
+    }
+    // The ranges are counted with --range, which does not take these options
+    if split_parts.is_some() {
+        reject_options(
+            &matches,
+            &["seed", "samples", "nth", "values", "rows"],
+            "The split command",
+        );
+    }
+    if let Some(solution) = &rank_solution {
+        match value_t!(matches, "size", usize) {
+            Ok(size) if size != solution.len() => {
+                eprintln!(
+                    "The solution has {} queens instead of {}.",
+                    solution.len(),
+                    size
+                );
+                std::process::exit(1);
+            }
+            _ => (),
+        }
+    }
+    if let Some(command @ ("dimacs" | "model")) = matches.subcommand_name() {
+        if matches.is_present("fundamental") {
+            eprintln!(
+                "The symmetry reduction cannot be used with the {} command.",
+                command
+            );
+            std::process::exit(1);
+        }
+    }
+    if matches.subcommand_name() == Some("tree") {
+        reject_options(&matches, &["resume", "nth", "samples"], "The tree command");
+    }
+    if matches.subcommand_name() == Some("selftest") {
+        let unsupported = [
+            "resume",
+            "size",
+            "board",
+            "fixed",
+            "fundamental",
+            "variant",
+            "piece",
+            "samples",
+            "nth",
+            "range",
+            "checkpoint",
+            "stats",
+            "stats-file",
+        ];
+        reject_options(&matches, &unsupported, "The selftest command");
+        if matches.value_of("driver") == Some("3D") {
+            eprintln!("The selftest command only writes in the console.");
+            std::process::exit(1);
+        }
+    }
+    let complete_prefix = matches.subcommand_matches("complete").map(|complete| {
+        match complete.value_of("prefix").map(str::trim) {
+            None | Some("") => Vec::new(),
+            Some(prefix) => match parse_solution(prefix) {
+                Some(prefix) => prefix,
+                None => {
+                    eprintln!("Invalid prefix '{}', expected for instance '0,4'.", prefix);
+                    std::process::exit(1);
+                }
+            },
+        }
+    });
+    if complete_prefix.is_some() {
+        let unsupported = [
+            "resume",
+            "fundamental",
+            "samples",
+            "nth",
+            "range",
+            "checkpoint",
+            "stats",
+            "stats-file",
+        ];
+        reject_options(&matches, &unsupported, "The complete command");
+        if matches.value_of("driver") == Some("3D") {
+            eprintln!("The complete command only writes in the console.");
+            std::process::exit(1);
+        }
+    }
+    if samples.is_some() && matches.value_of("driver") == Some("3D") {
+        eprintln!("The samples are only written in the console.");
+        std::process::exit(1);
+    }
+
+    let solver = match matches.value_of("resume") {
+        Some(path) => match Solver::from_checkpoint(Path::new(path)) {
+            Ok(solver) => solver,
+            Err(error) => {
+                eprintln!("Could not resume from '{}': {}", path, error);
+                std::process::exit(1);
+            }
+        },
+        None => {
+            let board = match matches.value_of("board") {
+                Some(path) => match fs::read_to_string(path)
+                    .map_err(|error| error.to_string())
+                    .and_then(|content| content.parse::<Board>())
+                {
+                    Ok(board) => Some(board),
+                    Err(error) => {
+                        eprintln!("Could not read the board '{}': {}", path, error);
+                        std::process::exit(1);
+                    }
+                },
+                None => None,
+            };
+            let board_size = match &board {
+                Some(board) => board.size,
+                None => match &rank_solution {
+                    Some(solution) => solution.len(),
+                    None if matches.is_present("size") => match value_t!(matches, "size", usize) {
+                        Ok(size) => size,
+                        Err(_) => {
+                            eprintln!("The size of the board must be a non-negative integer.");
+                            std::process::exit(1);
+                        }
+                    },
+                    None => DEFAULT_BOARD_SIZE,
+                },
+            };
+            let mut options = SolverOptions::new(board_size);
+            if let Ok(backend) = value_t!(matches, "backend", Backend) {
+                options.backend = backend;
+            }
+            options.variant = value_t!(matches, "variant", Variant).unwrap_or(Variant::Classic);
+            options.piece = match matches.value_of("piece") {
+                Some(piece) => match piece.parse::<Piece>() {
+                    Ok(piece) => piece,
+                    Err(_) => {
+                        eprintln!("Invalid piece '{}'.", piece);
+                        std::process::exit(1);
+                    }
+                },
+                None => Piece::queen(),
+            };
+            options.symmetry_reduction = matches.is_present("fundamental");
+            if let Some(board) = board {
+                options.blocked_squares = board.blocked;
+            }
+            if let Some(fixed) = matches.value_of("fixed") {
+                options.fixed_queens = match parse_queens(fixed) {
+                    Some(queens) => queens,
+                    None => {
+                        eprintln!(
+                            "Invalid fixed queens '{}', expected for instance '0:3,5:1'.",
+                            fixed
+                        );
+                        std::process::exit(1);
+                    }
+                };
+            }
+            let mut solver = match Solver::try_new(&options) {
+                Ok(solver) => solver,
+                Err(error) => {
+                    eprintln!("Invalid options: {}.", error);
+                    std::process::exit(exit_code(&error));
+                }
+            };
+            if !options.variant.has_solutions(board_size, &options.piece) {
+                println!(
+                    "The {} variant has no solution for size {}.",
+                    options.variant.to_str(),
+                    board_size
+                );
+            }
+            if let Some((start, end)) = &range {
+                solver = match solver.with_prefix_range(start, end) {
+                    Ok(solver) => solver,
+                    Err(error) => {
+                        eprintln!("Invalid range: {}.", error);
+                        std::process::exit(1);
+                    }
+                };
+            }
+            solver
+        }
+    };
+    let board_size = solver.board_size();
+
+    if let Some(parts) = split_parts {
+        for part in solver.split_ranges(parts) {
+            let (start, end) = part.prefix_range();
+            println!("{}", format_range(start, end));
+        }
+        return;
+    }
+
+    if let Some(solution) = &rank_solution {
+        match Ranking::new(&solver).rank(solution) {
+            Some(rank) => println!("Rank of the solution: {}", rank),
+            None if solver.reduces_symmetries() => {
+                eprintln!(
+                    "{:?} is not the representative of a fundamental solution.",
+                    solution
+                );
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("{:?} is not a solution.", solution);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(prefix) = &complete_prefix {
+        let mut completions = match Completions::new(&solver) {
+            Some(completions) => completions,
+            None => {
+                eprintln!("Completions can only be counted on boards up to 64 and for pieces without leaps.");
+                std::process::exit(1);
+            }
+        };
+        let count = match completions.count(prefix) {
+            Some(count) => count,
+            None => {
+                eprintln!(
+                    "The queens {:?} cannot be placed on the first rows.",
+                    prefix
+                );
+                std::process::exit(1);
+            }
+        };
+        println!("Number of solutions extending {:?}: {}", prefix, count);
+        if let Some(counts) = completions.next_row(prefix) {
+            println!("By column of the queen of row {}:", prefix.len());
+            for (column, count) in counts.iter().enumerate() {
+                println!("{:>3}: {}", column, count);
+            }
+        }
+        return;
+    }
+
+    if let Some(dimacs) = matches.subcommand_matches("dimacs") {
+        let cnf = dimacs::encode(&solver);
+        match dimacs.value_of("file") {
+            Some(path) => {
+                if let Err(error) = fs::write(path, cnf) {
+                    eprintln!("Could not write '{}': {}", path, error);
+                    std::process::exit(1);
+                }
+            }
+            None => print!("{}", cnf),
+        }
+        return;
+    }
+
+    if let Some(model) = matches.subcommand_matches("model") {
+        let path = model.value_of("file").unwrap();
+        let model = match fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|content| content.parse::<Model>())
+        {
+            Ok(model) => model,
+            Err(error) => {
+                eprintln!("Could not read the model '{}': {}", path, error);
+                std::process::exit(1);
+            }
+        };
+        if let Model::Unsatisfiable = model {
+            println!("No solution!");
+            return;
+        }
+        match model.configuration(&solver) {
+            Ok(config) => {
+                ConsoleDriver::new(board_size).display(&config);
+                if !config.is_valid {
+                    eprintln!("The model is not a solution.");
+                    std::process::exit(1);
+                }
+            }
+            Err(error) => {
+                eprintln!("Invalid model: {}.", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(samples) = samples {
+        println!("Searching for size {}...", board_size);
+        let driver = ConsoleDriver::new(board_size);
+        let solutions = solver.sample_solutions(samples, seed);
+        for config in &solutions {
+            driver.display(config);
+        }
+        if solutions.is_empty() {
+            println!("No solution!");
+        } else if solver.reduces_symmetries() {
+            println!("Number of fundamental solutions found: {}", solutions.len());
+        } else {
+            println!("Number of solutions found: {}", solutions.len());
+        }
+        return;
+    }
+
+    if let Algorithm::MinConflicts | Algorithm::Construct = algorithm {
+        println!("Searching for size {}...", board_size);
+        let config = match algorithm {
+            Algorithm::Construct => construct::solve(board_size),
+            _ => min_conflicts::solve(board_size, seed),
+        };
+        match config {
+            Some(config) => {
+                ConsoleDriver::new(board_size).display(&config);
+                if !solver.verify(&config.configuration) {
+                    eprintln!("The placement found is not a solution.");
+                    std::process::exit(1);
+                }
+            }
+            None if solver.variant().has_solutions(board_size, solver.piece()) => {
+                eprintln!("The local search gave up without finding a solution.");
+                std::process::exit(1);
+            }
+            None => println!("No solution!"),
+        }
+        return;
+    }
+    let value_order = match value_t!(matches, "values", ValueOrder) {
+        Ok(ValueOrder::Random(_)) => ValueOrder::Random(seed),
+        Ok(order) => order,
+        Err(_) if matches.is_present("seed") => ValueOrder::Random(seed),
+        Err(_) => ValueOrder::LeftToRight,
+    };
+    let row_order = value_t!(matches, "rows", RowOrder).unwrap_or(RowOrder::TopDown);
+    let solver = match value_order {
+        ValueOrder::LeftToRight => solver,
+        order => solver.with_value_order(order),
+    };
+    let solver = match row_order {
+        RowOrder::TopDown => solver,
+        order => solver.with_row_order(order),
+    };
+    // Kept for the 3D driver, which can page through the solutions from there
+    let mut ranking = Ranking::new(&solver).with_cache();
+    let solver = match nth {
+        Some(index) => match ranking.solver_at(index) {
+            Some(solver) => solver,
+            None => {
+                eprintln!("There is no solution at index {}.", index);
+                std::process::exit(1);
+            }
+        },
+        None => solver,
+    };
+    if let Some(tree) = matches.subcommand_matches("tree") {
+        let mut solver = solver;
+        let dot = match dot::search_tree(&mut solver) {
+            Ok(dot) => dot,
+            Err(error) => {
+                eprintln!("Could not draw the search tree: {}.", error);
+                std::process::exit(1);
+            }
+        };
+        match tree.value_of("file") {
+            Some(path) => {
+                if let Err(error) = fs::write(path, dot) {
+                    eprintln!("Could not write '{}': {}", path, error);
+                    std::process::exit(1);
+                }
+            }
+            None => print!("{}", dot),
+        }
+        return;
+    }
+
+    let stats_file = matches.value_of("stats-file");
+    let solver = if matches.is_present("stats") || stats_file.is_some() {
+        solver.with_statistics()
+    } else {
+        solver
+    };
+
+    let threads = if matches.is_present("threads") {
+        match value_t!(matches, "threads", usize) {
+            Ok(threads) if threads > 0 => threads,
+            _ => {
+                eprintln!("The number of threads must be a positive integer.");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        1
+    };
+
+    if let Some(selftest) = matches.subcommand_matches("selftest") {
+        let max_size = match selftest.value_of("size") {
+            Some(_) => match value_t!(selftest, "size", usize) {
+                Ok(size) if size > 0 && size <= selftest::MAX_SIZE => size,
+                _ => {
+                    eprintln!(
+                        "The largest size must be between 1 and {}.",
+                        selftest::MAX_SIZE
+                    );
+                    std::process::exit(1);
+                }
+            },
+            None => 12,
+        };
+        let backend = value_t!(matches, "backend", Backend).unwrap_or(Backend::Bitboard);
+        let configure = |solver: Solver| {
+            let solver = match value_order {
+                ValueOrder::LeftToRight => solver,
+                order => solver.with_value_order(order),
+            };
+            match row_order {
+                RowOrder::TopDown => solver,
+                order => solver.with_row_order(order),
+            }
+        };
+        println!(
+            "Checking the numbers of solutions up to size {}...",
+            max_size
+        );
+        let mut mismatches = 0;
+        for board_size in 1..=max_size {
+            let check = selftest::check(board_size, backend, configure, threads);
+            let status = if check.is_ok() { "ok" } else { "MISMATCH" };
+            println!(
+                "{:>2}: {} solutions, {} fundamental solutions ({} in total), {} in {:.3} s",
+                board_size,
+                check.solutions,
+                check.fundamental_solutions,
+                check.total_solutions,
+                status,
+                check.elapsed.as_secs_f64()
+            );
+            if !check.is_ok() {
+                mismatches += 1;
+                println!(
+                    "    expected {} solutions and {} fundamental solutions",
+                    check.expected_solutions(),
+                    check.expected_fundamental_solutions()
+                );
+            }
+        }
+        if mismatches > 0 {
+            eprintln!(
+                "{} of the {} sizes checked do not match.",
+                mismatches, max_size
+            );
+            std::process::exit(1);
+        }
+        println!("All the sizes match.");
+        return;
+    }
+
+    let checkpoint = matches.value_of("checkpoint").map(PathBuf::from);
+    if checkpoint.is_some() && threads > 1 {
+        eprintln!("A checkpoint cannot be saved when counting with several threads.");
+        std::process::exit(1);
+    }
 
     let param = StrategyParameters {
         stop_after_first_solution: matches.is_present("stop-after-first"),
+        threads,
+        checkpoint,
     };
 
     let interaction =
@@ -81,17 +963,31 @@ fn main() {
 
     let strategy_selection = value_t!(matches, "strategy", StrategySelection)
         .unwrap_or(StrategySelection::SolutionsWithSteps);
-    match strategy_selection {
+    // The 3D driver pages through the solutions when they come in
+    // lexicographic order
+    let (range_start, range_end) = solver.prefix_range();
+    let paging = if matches.value_of("driver") == Some("3D")
+        && solver.value_order() == ValueOrder::LeftToRight
+        && solver.row_order() == RowOrder::TopDown
+        && range_start.is_empty()
+        && range_end.is_empty()
+        && solver.backend() != Backend::DancingLinks
+    {
+        Some(ranking)
+    } else {
+        None
+    };
+    let solver = match strategy_selection {
         StrategySelection::AllSteps => {
             let driver_selection = matches.value_of("driver").unwrap_or("Console");
             match driver_selection {
                 "3D" => {
-                    let mut driver = Driver3D::new(board_size);
-                    driver.execute(solver, EachPartialStep::new(param), interaction);
+                    let mut driver = Driver3D::new(board_size).with_paging(paging);
+                    driver.execute(solver, EachPartialStep::new(param), interaction)
                 }
-                "Console" | _ => {
+                _ => {
                     let mut driver = ConsoleDriver::new(board_size);
-                    driver.execute(solver, EachPartialStep::new(param), interaction);
+                    driver.execute(solver, EachPartialStep::new(param), interaction)
                 }
             }
         }
@@ -99,40 +995,47 @@ fn main() {
             let driver_selection = matches.value_of("driver").unwrap_or("Console");
             match driver_selection {
                 "3D" => {
-                    let mut driver = Driver3D::new(board_size);
-                    driver.execute(solver, EachSolutionPartialSteps::new(param), interaction);
+                    let mut driver = Driver3D::new(board_size).with_paging(paging);
+                    driver.execute(solver, EachSolutionPartialSteps::new(param), interaction)
                 }
-                "Console" | _ => {
+                _ => {
                     let mut driver = ConsoleDriver::new(board_size);
-                    driver.execute(solver, EachSolutionPartialSteps::new(param), interaction);
+                    driver.execute(solver, EachSolutionPartialSteps::new(param), interaction)
                 }
-            };
+            }
         }
         StrategySelection::OnlySolutions => {
             let driver_selection = matches.value_of("driver").unwrap_or("Console");
             match driver_selection {
                 "3D" => {
-                    let mut driver = Driver3D::new(board_size);
-                    driver.execute(solver, EachSolution::new(param), interaction);
+                    let mut driver = Driver3D::new(board_size).with_paging(paging);
+                    driver.execute(solver, EachSolution::new(param), interaction)
                 }
-                "Console" | _ => {
+                _ => {
                     let mut driver = ConsoleDriver::new(board_size);
-                    driver.execute(solver, EachSolution::new(param), interaction);
+                    driver.execute(solver, EachSolution::new(param), interaction)
                 }
-            };
+            }
         }
         StrategySelection::OnlyCount => {
             let driver_selection = matches.value_of("driver").unwrap_or("Console");
             match driver_selection {
                 "3D" => {
-                    let mut driver = Driver3D::new(board_size);
-                    driver.execute(solver, CountSolutions::new(param), interaction);
+                    let mut driver = Driver3D::new(board_size).with_paging(paging);
+                    driver.execute(solver, CountSolutions::new(param), interaction)
                 }
-                "Console" | _ => {
+                _ => {
                     let mut driver = ConsoleDriver::new(board_size);
-                    driver.execute(solver, CountSolutions::new(param), interaction);
+                    driver.execute(solver, CountSolutions::new(param), interaction)
                 }
-            };
+            }
         }
     };
+
+    if let (Some(path), Some(statistics)) = (stats_file, solver.statistics()) {
+        if let Err(error) = fs::write(path, statistics.to_json() + "\n") {
+            eprintln!("Could not write the statistics in '{}': {}", path, error);
+            std::process::exit(1);
+        }
+    }
 }
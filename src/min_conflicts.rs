@@ -0,0 +1,192 @@
+use crate::random::Random;
+use crate::solver::Configuration;
+
+// Tries to place each queen on a diagonal free of other queens before giving
+// up and taking any column
+const PLACEMENT_TRIES: usize = 64;
+// Random partners tried for each queen in conflict during a pass
+const SWAP_TRIES: usize = 32;
+// Passes without any improvement before a restart
+const MAX_STALLED_PASSES: usize = 16;
+// Random placements tried before giving up
+const MAX_ATTEMPTS: usize = 1000;
+
+// The queens always form a permutation, so only the diagonals can be in
+// conflict. The number of conflicts is the number of pairs of queens sharing a
+// diagonal.
+struct Board {
+    columns: Vec<usize>,
+    down: Vec<u32>,
+    up: Vec<u32>,
+    conflicts: u64,
+}
+
+impl Board {
+    fn new(board_size: usize) -> Self {
+        Self {
+            columns: (0..board_size).collect(),
+            down: vec![0; 2 * board_size - 1],
+            up: vec![0; 2 * board_size - 1],
+            conflicts: 0,
+        }
+    }
+
+    fn diagonals(&self, i: usize, j: usize) -> (usize, usize) {
+        (i + j, i + self.columns.len() - j - 1)
+    }
+
+    fn add(&mut self, i: usize) {
+        let (d, u) = self.diagonals(i, self.columns[i]);
+        self.conflicts += (self.down[d] + self.up[u]) as u64;
+        self.down[d] += 1;
+        self.up[u] += 1;
+    }
+
+    fn remove(&mut self, i: usize) {
+        let (d, u) = self.diagonals(i, self.columns[i]);
+        self.down[d] -= 1;
+        self.up[u] -= 1;
+        self.conflicts -= (self.down[d] + self.up[u]) as u64;
+    }
+
+    fn is_attacked(&self, i: usize) -> bool {
+        let (d, u) = self.diagonals(i, self.columns[i]);
+        self.down[d] > 1 || self.up[u] > 1
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.remove(i);
+        self.remove(j);
+        self.columns.swap(i, j);
+        self.add(i);
+        self.add(j);
+    }
+
+    // Places the queens row after row, each on a random column left whose
+    // diagonals are free if one is found quickly enough.
+    // Returns the rows of the queens in conflict.
+    fn place_randomly(&mut self, random: &mut Random) -> Vec<usize> {
+        let n = self.columns.len();
+        for i in 0..n {
+            for _ in 0..PLACEMENT_TRIES {
+                let k = i + random.below(n - i);
+                self.columns.swap(i, k);
+                let (d, u) = self.diagonals(i, self.columns[i]);
+                if self.down[d] == 0 && self.up[u] == 0 {
+                    break;
+                }
+            }
+            self.add(i);
+        }
+        (0..n).filter(|&i| self.is_attacked(i)).collect()
+    }
+}
+
+// Finds one solution with a min-conflicts local search: while some queens are
+// in conflict, each of them swaps its column with a random other queen if it
+// reduces the number of conflicts. When the search stalls, it restarts from a
+// new random placement. The same seed always gives the same solution.
+// Returns `None` for the sizes without solution, or if no solution is found
+// after `MAX_ATTEMPTS` placements.
+pub fn solve(board_size: usize, seed: u64) -> Option<Configuration> {
+    solve_within(board_size, seed, MAX_ATTEMPTS)
+}
+
+// Same as `solve`, giving up after `attempts` random placements
+pub fn solve_within(board_size: usize, seed: u64, attempts: usize) -> Option<Configuration> {
+    if board_size == 0 || board_size == 2 || board_size == 3 {
+        return None;
+    }
+
+    let mut random = Random::new(seed);
+    for _ in 0..attempts {
+        let mut board = Board::new(board_size);
+        let mut attacked = board.place_randomly(&mut random);
+        let mut stalled_passes = 0;
+
+        // Each pair of queens in conflict always has one of them in `attacked`
+        while board.conflicts > 0 && stalled_passes < MAX_STALLED_PASSES {
+            let conflicts = board.conflicts;
+            let mut next_attacked = Vec::new();
+            for i in attacked {
+                if !board.is_attacked(i) {
+                    continue;
+                }
+                for _ in 0..SWAP_TRIES {
+                    let j = random.below(board_size);
+                    if j == i {
+                        continue;
+                    }
+                    let before = board.conflicts;
+                    board.swap(i, j);
+                    if board.conflicts < before {
+                        if board.is_attacked(j) {
+                            next_attacked.push(j);
+                        }
+                        break;
+                    }
+                    board.swap(i, j);
+                }
+                if board.is_attacked(i) {
+                    next_attacked.push(i);
+                }
+            }
+            attacked = next_attacked;
+            if board.conflicts < conflicts {
+                stalled_passes = 0;
+            } else {
+                stalled_passes += 1;
+            }
+        }
+
+        if board.conflicts == 0 {
+            return Some(Configuration {
+                configuration: board.columns,
+                is_valid: true,
+                symmetry_order: None,
+                rows: None,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn solutions_follow_the_seed() {
+        for n in (4..=30).chain([1, 1000]) {
+            let solver = Solver::new(n);
+            for seed in 0..10 {
+                let solution = solve(n, seed).unwrap();
+                assert!(solver.verify(&solution.configuration), "{} {}", n, seed);
+                assert_eq!(
+                    solve(n, seed).unwrap().configuration,
+                    solution.configuration
+                );
+            }
+        }
+        for n in [0, 2, 3] {
+            assert!(solve(n, 0).is_none());
+        }
+    }
+
+    #[test]
+    fn search_gives_up_after_the_attempts() {
+        assert!(solve_within(8, 0, 0).is_none());
+        // Most first placements of 6 queens stall
+        let solver = Solver::new(6);
+        let mut given_up = 0;
+        for seed in 0..20 {
+            match solve_within(6, seed, 1) {
+                Some(solution) => assert!(solver.verify(&solution.configuration)),
+                None => given_up += 1,
+            }
+            assert!(solve(6, seed).is_some());
+        }
+        assert!(given_up > 0);
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::solver::Solver;
+
+// Enough subtrees per thread so that a few long subtrees do not leave the
+// other threads idle at the end
+const SUBTREES_PER_THREAD: usize = 4;
+
+// Counts all the remaining solutions of `solver` using `threads` threads.
+// The search tree is split by the placement of the first one or two queens,
+// and each subtree is exhausted by the first available thread.
+// The solutions are added to the ones already found by `solver`, which is
+// finished afterwards.
+pub fn count_solutions(solver: &mut Solver, threads: usize) -> u64 {
+    let threads = threads.max(1);
+    let depth = if solver.board_size() >= SUBTREES_PER_THREAD * threads {
+        1
+    } else {
+        2
+    };
+
+    let subtrees = Arc::new(Mutex::new(solver.split(depth)));
+    let workers = (0..threads)
+        .map(|_| {
+            let subtrees = Arc::clone(&subtrees);
+            thread::spawn(move || {
+                let (mut found, mut total) = (0, 0);
+                let mut statistics = Vec::new();
+                loop {
+                    let subtree = subtrees.lock().unwrap().pop();
+                    match subtree {
+                        Some(mut subtree) => {
+                            while subtree.count_next_subtree(0) {}
+                            found += subtree.solutions_found();
+                            total += subtree.total_solutions();
+                            statistics.extend(subtree.statistics().cloned());
+                        }
+                        None => return (found, total, statistics),
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for worker in workers {
+        let (found, total, statistics) = worker.join().unwrap();
+        solver.add_solutions(found, total);
+        for statistics in &statistics {
+            solver.add_statistics(statistics);
+        }
+    }
+    solver.solutions_found()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Backend;
+
+    const BACKENDS: [Backend; 3] = [Backend::Vectors, Backend::Bitboard, Backend::DancingLinks];
+
+    // The solvers to count on a board: every solution, the symmetry reduction
+    // and a few sets of fixed queens
+    fn solvers(board_size: usize, backend: Backend) -> Vec<Solver> {
+        let n = board_size;
+        let mut solvers = vec![
+            Solver::with_backend(n, backend),
+            Solver::with_backend(n, backend).with_symmetry_reduction(),
+        ];
+        if n >= 4 {
+            for fixed in [&[(n / 2, 0)][..], &[(0, 1), (n - 1, n - 2)][..]] {
+                solvers.push(
+                    Solver::with_backend(n, backend)
+                        .with_fixed_queens(fixed)
+                        .unwrap(),
+                );
+            }
+        }
+        solvers
+    }
+
+    fn sequential_counts(mut solver: Solver) -> (u64, u64) {
+        while solver.next_placement().is_some() {}
+        (solver.solutions_found(), solver.total_solutions())
+    }
+
+    #[test]
+    fn threads_count_as_the_sequential_search() {
+        for backend in BACKENDS {
+            for board_size in 1..=10 {
+                for solver in solvers(board_size, backend) {
+                    let expected = sequential_counts(solver.clone());
+                    // Enough threads to split the board on one and two rows
+                    for threads in [1, 2, 3, 8] {
+                        let mut counted = solver.clone();
+                        let found = count_solutions(&mut counted, threads);
+                        assert_eq!(
+                            (found, counted.total_solutions()),
+                            expected,
+                            "{:?} with {:?} on {} with {} threads",
+                            backend,
+                            solver.fixed_queens(),
+                            board_size,
+                            threads
+                        );
+                        assert!(counted.is_finished());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn threads_add_to_the_solutions_already_found() {
+        for backend in BACKENDS {
+            for solver in solvers(8, backend) {
+                let expected = sequential_counts(solver.clone());
+                let mut counted = solver;
+                // Stops in the middle of the search, after a few solutions
+                while counted.solutions_found() < expected.0.min(2) {
+                    counted.search();
+                }
+                assert_eq!(count_solutions(&mut counted, 3), expected.0);
+                assert_eq!(counted.total_solutions(), expected.1);
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+use std::str::FromStr;
+
+// The attacks of a piece. The solver always places one piece per row, so the
+// attacks along the rows do not matter.
+// A piece can attack along the columns and the diagonals, like a rook or a
+// bishop, and leap to the squares at a given distance, like a knight.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Piece {
+    pub columns: bool,
+    pub diagonals: bool,
+    // A leap (a, b) reaches the squares a rows and b columns away, or b rows
+    // and a columns away, in every direction. Stored with a <= b, sorted.
+    leaps: Vec<(usize, usize)>,
+}
+
+// Leapers of the Betza notation
+const LEAPERS: [(char, (usize, usize)); 9] = [
+    ('W', (0, 1)),
+    ('F', (1, 1)),
+    ('D', (0, 2)),
+    ('N', (1, 2)),
+    ('A', (2, 2)),
+    ('H', (0, 3)),
+    ('C', (1, 3)),
+    ('Z', (2, 3)),
+    ('G', (3, 3)),
+];
+
+impl Piece {
+    pub fn new(columns: bool, diagonals: bool, leaps: &[(usize, usize)]) -> Self {
+        let mut leaps = leaps
+            .iter()
+            .map(|&(a, b)| (a.min(b), a.max(b)))
+            .filter(|&leap| leap != (0, 0))
+            .collect::<Vec<(usize, usize)>>();
+        leaps.sort_unstable();
+        leaps.dedup();
+        Self {
+            columns,
+            diagonals,
+            leaps,
+        }
+    }
+
+    pub fn queen() -> Self {
+        Self::new(true, true, &[])
+    }
+
+    pub fn rook() -> Self {
+        Self::new(true, false, &[])
+    }
+
+    pub fn bishop() -> Self {
+        Self::new(false, true, &[])
+    }
+
+    // Also known as the amazon: a queen which can also move as a knight
+    pub fn superqueen() -> Self {
+        Self::new(true, true, &[(1, 2)])
+    }
+
+    pub fn leaps(&self) -> &[(usize, usize)] {
+        &self.leaps
+    }
+
+    // The moves of all the leaps, as (rows, columns)
+    pub fn leap_offsets(&self) -> Vec<(isize, isize)> {
+        let mut offsets = Vec::new();
+        for &(a, b) in &self.leaps {
+            let (a, b) = (a as isize, b as isize);
+            for &(x, y) in &[(a, b), (b, a)] {
+                for &(sx, sy) in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                    offsets.push((sx * x, sy * y));
+                }
+            }
+        }
+        offsets.sort_unstable();
+        offsets.dedup();
+        offsets
+    }
+
+    // Whether the piece looks the same after a rotation or a reflection of the
+    // board, which is needed by the symmetry reduction. Since the pieces are
+    // placed one per row, the attacks must include the columns.
+    pub fn is_symmetric(&self) -> bool {
+        self.columns
+    }
+}
+
+// Written in a subset of the Betza notation: 'Q', 'R' or 'B' for the lines,
+// followed by the letters of the leapers, or (a,b) for the other leaps.
+// For instance, the superqueen is 'QN'.
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.columns, self.diagonals) {
+            (true, true) => write!(f, "Q")?,
+            (true, false) => write!(f, "R")?,
+            (false, true) => write!(f, "B")?,
+            (false, false) => (),
+        }
+        for leap in &self.leaps {
+            match LEAPERS.iter().find(|(_, l)| l == leap) {
+                Some((letter, _)) => write!(f, "{}", letter)?,
+                None => write!(f, "({},{})", leap.0, leap.1)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Piece {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "queen" => return Ok(Piece::queen()),
+            "rook" => return Ok(Piece::rook()),
+            "bishop" => return Ok(Piece::bishop()),
+            "superqueen" | "amazon" => return Ok(Piece::superqueen()),
+            _ => (),
+        }
+
+        let (mut columns, mut diagonals) = (false, false);
+        let mut leaps = Vec::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                'Q' => {
+                    columns = true;
+                    diagonals = true;
+                }
+                'R' => columns = true,
+                'B' => diagonals = true,
+                '(' => {
+                    let leap = chars.by_ref().take_while(|&c| c != ')').collect::<String>();
+                    let mut distances = leap.splitn(2, ',').map(|x| x.trim().parse::<usize>());
+                    match (distances.next(), distances.next()) {
+                        (Some(Ok(a)), Some(Ok(b))) => leaps.push((a, b)),
+                        _ => return Err("invalid leap"),
+                    }
+                }
+                _ => match LEAPERS.iter().find(|(letter, _)| *letter == c) {
+                    Some((_, leap)) => leaps.push(*leap),
+                    None => return Err("no match"),
+                },
+            }
+        }
+        if s.is_empty() {
+            return Err("no match");
+        }
+        Ok(Piece::new(columns, diagonals, &leaps))
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use crate::solver::{Configuration, Solver};
+use crate::symmetry;
+
+// Finds the solutions by their index in lexicographic order, and the index of
+// a solution, without going through the solutions before it: the solutions
+// under each placement of the next queen are counted, and whole subtrees are
+// skipped at once. The indices start from 0.
+// With the symmetry reduction, only the representatives are indexed.
+pub struct Ranking {
+    solver: Solver,
+    // Counts of the prefixes already explored, kept when enabled
+    cache: Option<HashMap<Vec<usize>, (u64, u64)>>,
+}
+
+impl Ranking {
+    // Uses the rules of `solver`, whose search is left untouched
+    pub fn new(solver: &Solver) -> Self {
+        Self {
+            solver: solver.clone(),
+            cache: None,
+        }
+    }
+
+    // Keeps the counts of the subtrees, so that looking for nearby solutions
+    // again, for instance when paging through them, does not count them again
+    pub fn with_cache(mut self) -> Self {
+        self.cache = Some(HashMap::new());
+        self
+    }
+
+    // Number of solutions completing the queens given for the first rows, as
+    // (solutions found, total solutions)
+    pub fn count(&mut self, prefix: &[usize]) -> (u64, u64) {
+        if let Some(count) = self.cache.as_ref().and_then(|cache| cache.get(prefix)) {
+            return *count;
+        }
+        let count = self.solver.count_completions(prefix);
+        if let Some(cache) = &mut self.cache {
+            cache.insert(prefix.to_vec(), count);
+        }
+        count
+    }
+
+    // The solution at `index`, along with the total number of solutions before
+    // it
+    fn locate(&mut self, index: u64) -> Option<(Vec<usize>, u64)> {
+        let n = self.solver.board_size();
+        let (mut index, mut total) = (index, 0);
+        let mut prefix = Vec::with_capacity(n);
+        while prefix.len() < n {
+            let mut found = false;
+            for j in 0..n {
+                prefix.push(j);
+                let count = self.count(&prefix);
+                if index < count.0 {
+                    found = true;
+                    break;
+                }
+                index -= count.0;
+                total += count.1;
+                prefix.pop();
+            }
+            if !found {
+                return None;
+            }
+        }
+        Some((prefix, total))
+    }
+
+    pub fn nth(&mut self, index: u64) -> Option<Configuration> {
+        let (configuration, _) = self.locate(index)?;
+        let symmetry_order = if self.solver.reduces_symmetries() {
+            symmetry::representative_order(&configuration)
+        } else {
+            None
+        };
+        Some(Configuration {
+            configuration,
+            is_valid: true,
+            symmetry_order,
+            rows: None,
+        })
+    }
+
+    // A solver whose next solution is the one at `index`, the solutions before
+    // it being counted as found
+    pub fn solver_at(&mut self, index: u64) -> Option<Solver> {
+        let (solution, total) = self.locate(index)?;
+        Some(self.solver.positioned_at(&solution, index, total))
+    }
+
+    // Returns `None` if `solution` is not a solution, or not a representative
+    // with the symmetry reduction
+    pub fn rank(&mut self, solution: &[usize]) -> Option<u64> {
+        if solution.len() != self.solver.board_size() || self.count(solution).0 == 0 {
+            return None;
+        }
+        let mut rank = 0;
+        let mut prefix = Vec::with_capacity(solution.len());
+        for &column in solution {
+            for j in 0..column {
+                prefix.push(j);
+                rank += self.count(&prefix).0;
+                prefix.pop();
+            }
+            prefix.push(column);
+        }
+        Some(rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_counts_give_the_same_solutions() {
+        let solver = Solver::new(8);
+        let mut cached = Ranking::new(&solver).with_cache();
+        // Back and forth, as when paging
+        for &index in &[50, 10, 91, 11, 49, 0] {
+            let solution = cached.nth(index).unwrap();
+            assert_eq!(
+                solution.configuration,
+                Ranking::new(&solver).nth(index).unwrap().configuration
+            );
+            assert_eq!(cached.rank(&solution.configuration), Some(index));
+        }
+        assert!(cached.nth(92).is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+
+use crate::solver::{Backend, Solver};
+use crate::strategies::CountSolutions;
+use crate::strategy::{Strategy, StrategyParameters};
+
+// Number of solutions for the sizes 1, 2, 3... (OEIS A000170)
+const SOLUTIONS: [u64; 27] = [
+    1,
+    0,
+    0,
+    2,
+    10,
+    4,
+    40,
+    92,
+    352,
+    724,
+    2680,
+    14200,
+    73712,
+    365596,
+    2279184,
+    14772512,
+    95815104,
+    666090624,
+    4968057848,
+    39029188884,
+    314666222712,
+    2691008701644,
+    24233937684440,
+    227514171973736,
+    2207893435808352,
+    22317699616364044,
+    234907967154122528,
+];
+
+// Number of fundamental solutions, up to the symmetries of the square
+// (OEIS A002562)
+const FUNDAMENTAL_SOLUTIONS: [u64; 27] = [
+    1,
+    0,
+    0,
+    1,
+    2,
+    1,
+    6,
+    12,
+    46,
+    92,
+    341,
+    1787,
+    9233,
+    45752,
+    285053,
+    1846955,
+    11977939,
+    83263591,
+    621012754,
+    4878666808,
+    39333324973,
+    336376244042,
+    3029242658210,
+    28439272956934,
+    275986683743434,
+    2789712466510289,
+    29363495934315694,
+];
+
+pub const MAX_SIZE: usize = SOLUTIONS.len();
+
+// The counts of one size, with and without the symmetry reduction
+pub struct Check {
+    pub board_size: usize,
+    pub solutions: u64,
+    pub fundamental_solutions: u64,
+    // Solutions equivalent to the fundamental ones
+    pub total_solutions: u64,
+    pub elapsed: Duration,
+}
+
+impl Check {
+    pub fn expected_solutions(&self) -> u64 {
+        SOLUTIONS[self.board_size - 1]
+    }
+
+    pub fn expected_fundamental_solutions(&self) -> u64 {
+        FUNDAMENTAL_SOLUTIONS[self.board_size - 1]
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.solutions == self.expected_solutions()
+            && self.total_solutions == self.expected_solutions()
+            && self.fundamental_solutions == self.expected_fundamental_solutions()
+    }
+}
+
+// Counts the solutions of a size with the strategy used by `--strat
+// OnlyCount`, once without and once with the symmetry reduction. `configure`
+// is applied last to each solver, for the options which must come after the
+// others such as the orders. The size must be between 1 and `MAX_SIZE`.
+pub fn check<F>(board_size: usize, backend: Backend, configure: F, threads: usize) -> Check
+where
+    F: Fn(Solver) -> Solver,
+{
+    let count = |mut solver: Solver| {
+        let mut strategy = CountSolutions::new(StrategyParameters {
+            stop_after_first_solution: false,
+            threads,
+            checkpoint: None,
+        });
+        while strategy.has_next_batch(&mut solver) {
+            while strategy.next_step(&mut solver).is_some() {}
+        }
+        (solver.solutions_found(), solver.total_solutions())
+    };
+
+    let start = Instant::now();
+    let (solutions, _) = count(configure(Solver::with_backend(board_size, backend)));
+    let (fundamental_solutions, total_solutions) = count(configure(
+        Solver::with_backend(board_size, backend).with_symmetry_reduction(),
+    ));
+    Check {
+        board_size,
+        solutions,
+        fundamental_solutions,
+        total_solutions,
+        elapsed: start.elapsed(),
+    }
+}
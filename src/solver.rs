@@ -1,35 +1,876 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::dancing_links::{DancingLinks, Step};
+use crate::piece::Piece;
+use crate::random::Random;
+use crate::statistics::Statistics;
+use crate::symmetry;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Backend {
+    Vectors,
+    Bitboard,
+    // Solves the exact cover problem of the rows, the columns and the attacks
+    // with dancing links, choosing each time the row or the column with the
+    // fewest free squares
+    DancingLinks,
+}
+
+impl Backend {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Backend::Vectors => "Vectors",
+            Backend::Bitboard => "Bitboard",
+            Backend::DancingLinks => "DancingLinks",
+        }
+    }
+
+    // The dancing links hold a node for each square of the board
+    pub fn max_board_size(&self) -> usize {
+        match self {
+            Backend::Vectors => 1 << 24,
+            Backend::Bitboard => 64,
+            Backend::DancingLinks => 1 << 10,
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Vectors" => Ok(Backend::Vectors),
+            "Bitboard" => Ok(Backend::Bitboard),
+            "DancingLinks" => Ok(Backend::DancingLinks),
+            _ => Err("no match"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Variant {
+    Classic,
+    // The diagonals wrap around the edges of the board
+    Toroidal,
+}
+
+impl Variant {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Variant::Classic => "Classic",
+            Variant::Toroidal => "Toroidal",
+        }
+    }
+
+    // False for the boards known to have no solution: toroidal boards only
+    // have solutions for the queens when their size is coprime with 6. Pieces
+    // attacking at least like the queen have no more solutions, and the other
+    // pieces are left to the search.
+    pub fn has_solutions(&self, board_size: usize, piece: &Piece) -> bool {
+        if !piece.columns || !piece.diagonals {
+            return true;
+        }
+        match self {
+            Variant::Classic => board_size != 2 && board_size != 3,
+            Variant::Toroidal => gcd(board_size, 6) == 1,
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl FromStr for Variant {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "classic" => Ok(Variant::Classic),
+            "toroidal" => Ok(Variant::Toroidal),
+            _ => Err("no match"),
+        }
+    }
+}
+
+// Order in which the columns of a row are tried
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ValueOrder {
+    LeftToRight,
+    // From the center of the row to its edges
+    MiddleOut,
+    // Shuffled with the given seed
+    Random(u64),
+    // The columns leaving the most free squares on the other rows first
+    LeastConstraining,
+}
+
+impl ValueOrder {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ValueOrder::LeftToRight => "LeftToRight",
+            ValueOrder::MiddleOut => "MiddleOut",
+            ValueOrder::Random(_) => "Random",
+            ValueOrder::LeastConstraining => "LeastConstraining",
+        }
+    }
+}
+
+impl FromStr for ValueOrder {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "LeftToRight" => Ok(ValueOrder::LeftToRight),
+            "MiddleOut" => Ok(ValueOrder::MiddleOut),
+            "Random" => Ok(ValueOrder::Random(0)),
+            "LeastConstraining" => Ok(ValueOrder::LeastConstraining),
+            _ => Err("no match"),
+        }
+    }
+}
+
+// Order in which the rows are filled
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RowOrder {
+    TopDown,
+    // The row with the fewest free columns first
+    MostConstrained,
+}
+
+impl RowOrder {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            RowOrder::TopDown => "TopDown",
+            RowOrder::MostConstrained => "MostConstrained",
+        }
+    }
+}
+
+impl FromStr for RowOrder {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TopDown" => Ok(RowOrder::TopDown),
+            "MostConstrained" => Ok(RowOrder::MostConstrained),
+            _ => Err("no match"),
+        }
+    }
+}
+
+// For the vectors, `true` means the line is free, and the blocked squares are
+// stored row after row, or not at all if there are none.
+// For the bitboard, a set bit means the line is attacked. The diagonals of the
+// bitboard are indexed so that the attacks on a row can be obtained by a shift.
+// The blocked squares are stored as one mask per row.
+// On toroidal boards, only the first `board_size` diagonals are used.
+#[derive(Clone)]
+enum Attacks {
+    Vectors {
+        col: Vec<bool>,
+        up: Vec<bool>,
+        down: Vec<bool>,
+        blocked: Vec<bool>,
+    },
+    Bitboard {
+        col: u64,
+        up: u128,
+        down: u128,
+        blocked: Vec<u64>,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PlacementError {
+    OutOfBoard(usize, usize),
+    SameRow(usize),
+    Blocked(usize, usize),
+    Attack((usize, usize), (usize, usize)),
+    // The given squares break the symmetries used by the symmetry reduction
+    SymmetryReduction,
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlacementError::OutOfBoard(i, j) => {
+                write!(f, "the queen on ({}, {}) is outside the board", i, j)
+            }
+            PlacementError::SameRow(i) => write!(f, "several queens are given on row {}", i),
+            PlacementError::Blocked(i, j) => {
+                write!(f, "the queen on ({}, {}) is on a blocked square", i, j)
+            }
+            PlacementError::Attack((i1, j1), (i2, j2)) => write!(
+                f,
+                "the queens on ({}, {}) and ({}, {}) attack each other",
+                i1, j1, i2, j2
+            ),
+            PlacementError::SymmetryReduction => {
+                write!(
+                    f,
+                    "the fixed queens and the blocked squares break the symmetries"
+                )
+            }
+        }
+    }
+}
+
+// Options of a solver which do not fit together, returned by `Solver::try_new`
+#[derive(Debug, PartialEq, Eq)]
+pub enum SolverError {
+    ZeroSize,
+    // The board size is beyond what the backend supports
+    TooLarge(usize, Backend),
+    // The symmetry reduction is asked for with a piece which does not attack
+    // along the columns
+    AsymmetricPiece(Piece),
+    // The symmetry reduction is asked for with fixed queens or blocked squares
+    BrokenSymmetry,
+    // The fixed queens or the blocked squares cannot be placed
+    InvalidPlacement(PlacementError),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolverError::ZeroSize => write!(f, "the board must have at least one row"),
+            SolverError::TooLarge(board_size, backend) => write!(
+                f,
+                "the {} backend only supports boards up to {}, not {}",
+                backend.to_str(),
+                backend.max_board_size(),
+                board_size
+            ),
+            SolverError::AsymmetricPiece(piece) => write!(
+                f,
+                "the symmetry reduction needs a piece attacking along the columns, unlike {}",
+                piece
+            ),
+            SolverError::BrokenSymmetry => PlacementError::SymmetryReduction.fmt(f),
+            SolverError::InvalidPlacement(error) => error.fmt(f),
+        }
+    }
+}
+
+// The rules of a solver, checked together by `Solver::try_new`
+#[derive(Clone, Debug)]
+pub struct SolverOptions {
+    pub board_size: usize,
+    pub backend: Backend,
+    pub variant: Variant,
+    pub piece: Piece,
+    pub symmetry_reduction: bool,
+    // As (row, column)
+    pub blocked_squares: Vec<(usize, usize)>,
+    pub fixed_queens: Vec<(usize, usize)>,
+}
+
+impl SolverOptions {
+    // Queens on a classic board, with the fastest backend for the size
+    pub fn new(board_size: usize) -> Self {
+        Self {
+            board_size,
+            backend: if board_size <= Backend::Bitboard.max_board_size() {
+                Backend::Bitboard
+            } else {
+                Backend::Vectors
+            },
+            variant: Variant::Classic,
+            piece: Piece::queen(),
+            symmetry_reduction: false,
+            blocked_squares: Vec::new(),
+            fixed_queens: Vec::new(),
+        }
+    }
+}
+
+// Rotations of the `n` lowest bits of a mask, with `k < n`
+pub(crate) fn rotate_left(mask: u64, k: usize, n: usize) -> u64 {
+    if k == 0 {
+        mask
+    } else {
+        let full = if n == 64 { !0 } else { (1u64 << n) - 1 };
+        ((mask << k) | (mask >> (n - k))) & full
+    }
+}
+
+pub(crate) fn rotate_right(mask: u64, k: usize, n: usize) -> u64 {
+    if k == 0 {
+        mask
+    } else {
+        let full = if n == 64 { !0 } else { (1u64 << n) - 1 };
+        ((mask >> k) | (mask << (n - k))) & full
+    }
+}
+
+const CHECKPOINT_HEADER: &str = "n-queens checkpoint 1";
+
+// Order in which the columns of each row are tried, when they are not tried
+// from left to right. The order of a row is computed again each time the
+// search enters it.
+#[derive(Clone)]
+struct ColumnOrder {
+    order: ValueOrder,
+    random: Random,
+    // Columns of each row, in the order they are tried
+    columns: Vec<Vec<usize>>,
+    // Position of each column in `columns`
+    positions: Vec<Vec<usize>>,
+}
+
+// Number of free squares of the empty rows on each line, so that the
+// LeastConstraining order does not look at the whole board for each column
+#[derive(Clone)]
+struct FreeLines {
+    columns: Vec<usize>,
+    // Indexed like `Solver::diagonals`
+    down: Vec<usize>,
+    up: Vec<usize>,
+    // Whether each row holds a queen, placed or fixed
+    filled: Vec<bool>,
+}
+
+// Prefixes per range when splitting the work, so that the ranges stay balanced
+// even if the subtrees under the prefixes have different sizes
+const PREFIXES_PER_RANGE: usize = 8;
+
+fn invalid_checkpoint(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid checkpoint: {}", reason),
+    )
+}
+
+#[derive(Clone)]
 pub struct Solver {
     board_size: usize,
     solutions_found: u64,
+    total_solutions: u64,
     finished: bool,
-    col: Vec<bool>,
-    up: Vec<bool>,
-    down: Vec<bool>,
+    symmetry_reduction: bool,
+    variant: Variant,
+    piece: Piece,
+    // Moves of the leaps of the piece, as (rows, columns)
+    leap_offsets: Vec<(isize, isize)>,
+    attacks: Attacks,
+    // Column of the queen given for each row, if any
+    fixed: Vec<Option<usize>>,
     context: Vec<(usize, usize)>,
+    // Number of rows at the beginning of `context` which are never backtracked
+    root_depth: usize,
+    // Set when the last position of `context` has been fully explored
+    backtrack_next: bool,
+    // Symmetry order of the last solution found with the symmetry reduction
+    symmetry_order: Option<u8>,
+    // Set when the columns are not tried from left to right
+    column_order: Option<ColumnOrder>,
+    row_order: RowOrder,
+    // Number of free columns of each row, kept up to date by `occupy` and
+    // `release` for the MostConstrained order when the piece does not leap.
+    // Empty otherwise.
+    free_counts: Vec<usize>,
+    // Kept up to date the same way for the LeastConstraining order
+    free_lines: Option<FreeLines>,
+    // Bounds, in lexicographic order, of the columns of the first rows.
+    // Empty when the search is not bounded.
+    range_start: Vec<usize>,
+    range_end: Vec<usize>,
+    // Set for the dancing links backend, which then runs the search. Its
+    // matrix is built again after each change of the rules. `context` holds
+    // the fixed queens and the queens it places, in order, followed by an
+    // unused position, and `attacks` only serves to check placements.
+    links: Option<DancingLinks>,
+    statistics: Option<Statistics>,
+    // Queens of the last complete placement given by `next_placement`, kept
+    // since the search lifts a queen at once if it is not reported
+    complete: Vec<(usize, usize)>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SearchEvent {
+    // A queen is placed on (row, column)
+    Place(usize, usize),
+    // The queen on (row, column) is lifted
+    Remove(usize, usize),
+    // No other column of the row can hold a queen
+    DeadEnd(usize),
+    // No other row of the column can hold a queen. Only given by the dancing
+    // links backend, which can also choose a column to fill.
+    ColumnDeadEnd(usize),
+    // The queens placed form a solution
+    Solution,
+    // The whole search space has been explored
+    Exhausted,
 }
 
 pub struct Configuration {
     pub configuration: Vec<usize>,
     pub is_valid: bool,
+    // Only given for the solutions found with the symmetry reduction
+    pub symmetry_order: Option<u8>,
+    // Row of each queen of `configuration`, only given when the queens are
+    // not on the first rows, in order
+    pub rows: Option<Vec<usize>>,
+}
+
+// A placement reached by the search, borrowed from the solver until the search
+// goes on. Nothing is allocated unless an owned `Configuration` is asked for.
+pub struct Placement<'a> {
+    // The queens as (row, column), in the order they were placed
+    pub queens: &'a [(usize, usize)],
+    pub is_valid: bool,
+    // Only given for the solutions found with the symmetry reduction
+    pub symmetry_order: Option<u8>,
+    board_size: usize,
+}
+
+impl Placement<'_> {
+    pub fn to_configuration(&self) -> Configuration {
+        let (configuration, rows) = columns_and_rows(self.queens, self.board_size);
+        Configuration {
+            configuration,
+            is_valid: self.is_valid,
+            symmetry_order: self.symmetry_order,
+            rows,
+        }
+    }
+}
+
+// The columns of the queens, row after row, along with their rows if they are
+// not the first ones in order
+fn columns_and_rows(
+    queens: &[(usize, usize)],
+    board_size: usize,
+) -> (Vec<usize>, Option<Vec<usize>>) {
+    if queens.iter().enumerate().all(|(k, &(i, _))| i == k) {
+        (queens.iter().map(|&(_, j)| j).collect(), None)
+    } else if queens.len() == board_size {
+        let mut configuration = vec![0; board_size];
+        for &(i, j) in queens {
+            configuration[i] = j;
+        }
+        (configuration, None)
+    } else {
+        (
+            queens.iter().map(|&(_, j)| j).collect(),
+            Some(queens.iter().map(|&(i, _)| i).collect()),
+        )
+    }
+}
+
+impl Configuration {
+    // The queens as (row, column)
+    pub fn queens(&self) -> Vec<(usize, usize)> {
+        match &self.rows {
+            Some(rows) => rows
+                .iter()
+                .copied()
+                .zip(self.configuration.iter().copied())
+                .collect(),
+            None => self.configuration.iter().copied().enumerate().collect(),
+        }
+    }
 }
 
 impl Solver {
     pub fn new(board_size: usize) -> Self {
-        let mut col = Vec::new();
-        col.resize(board_size, true);
-        let mut up = Vec::new();
-        up.resize(2 * board_size - 1, true);
-        let mut down = Vec::new();
-        down.resize(2 * board_size - 1, true);
+        Self::with_backend(board_size, Backend::Vectors)
+    }
 
-        Self {
+    // Checks the options instead of panicking, unlike the other constructors
+    pub fn try_new(options: &SolverOptions) -> Result<Self, SolverError> {
+        let (board_size, backend) = (options.board_size, options.backend);
+        if board_size == 0 {
+            return Err(SolverError::ZeroSize);
+        }
+        if board_size > backend.max_board_size() {
+            return Err(SolverError::TooLarge(board_size, backend));
+        }
+        if options.symmetry_reduction && !options.piece.is_symmetric() {
+            return Err(SolverError::AsymmetricPiece(options.piece.clone()));
+        }
+        if options.symmetry_reduction
+            && !(options.blocked_squares.is_empty() && options.fixed_queens.is_empty())
+        {
+            return Err(SolverError::BrokenSymmetry);
+        }
+        let solver = Self::with_backend(board_size, backend)
+            .with_variant(options.variant)
+            .with_piece(options.piece.clone())
+            .with_blocked_squares(&options.blocked_squares)
+            .and_then(|solver| solver.with_fixed_queens(&options.fixed_queens))
+            .map_err(SolverError::InvalidPlacement)?;
+        Ok(if options.symmetry_reduction {
+            solver.with_symmetry_reduction()
+        } else {
+            solver
+        })
+    }
+
+    pub fn with_backend(board_size: usize, backend: Backend) -> Self {
+        assert!(board_size > 0, "the board must have at least one row");
+        assert!(
+            board_size <= backend.max_board_size(),
+            "the {} backend supports boards up to {}",
+            backend.to_str(),
+            backend.max_board_size()
+        );
+
+        let attacks = match backend {
+            Backend::Vectors | Backend::DancingLinks => Attacks::Vectors {
+                col: vec![true; board_size],
+                up: vec![true; 2 * board_size - 1],
+                down: vec![true; 2 * board_size - 1],
+                blocked: Vec::new(),
+            },
+            Backend::Bitboard => Attacks::Bitboard {
+                col: 0,
+                up: 0,
+                down: 0,
+                blocked: vec![0; board_size],
+            },
+        };
+
+        let mut solver = Self {
             board_size,
             solutions_found: 0,
+            total_solutions: 0,
             finished: false,
-            col,
-            up,
-            down,
+            symmetry_reduction: false,
+            variant: Variant::Classic,
+            piece: Piece::queen(),
+            leap_offsets: Vec::new(),
+            attacks,
+            fixed: vec![None; board_size],
             context: vec![(0, 0)],
+            root_depth: 0,
+            backtrack_next: false,
+            symmetry_order: None,
+            column_order: None,
+            row_order: RowOrder::TopDown,
+            free_counts: Vec::new(),
+            free_lines: None,
+            range_start: Vec::new(),
+            range_end: Vec::new(),
+            links: None,
+            statistics: None,
+            complete: Vec::new(),
+        };
+        if backend == Backend::DancingLinks {
+            solver.build_links();
+        }
+        solver
+    }
+
+    // With the symmetry reduction, only one solution of each equivalence class
+    // under the symmetries of the square is reported: the smallest one in
+    // lexicographic order. The first queen is then always on the left half of
+    // the board, so the other half of the first row is not explored.
+    pub fn with_symmetry_reduction(mut self) -> Self {
+        assert!(
+            self.piece.is_symmetric(),
+            "the symmetry reduction needs a piece attacking along the columns"
+        );
+        assert!(
+            self.fixed.iter().all(Option::is_none),
+            "the fixed queens break the symmetries"
+        );
+        assert!(
+            self.blocked_squares().is_empty(),
+            "the blocked squares break the symmetries"
+        );
+        self.symmetry_reduction = true;
+        self.rebuild_links();
+        self
+    }
+
+    // Only the placements completing the given queens are explored. The queens
+    // can be on any row, and are given as (row, column).
+    // Must be called before the search starts, and cannot be used with the
+    // symmetry reduction since the given queens break the symmetries. The
+    // queens must not attack the ones fixed before.
+    pub fn with_fixed_queens(mut self, queens: &[(usize, usize)]) -> Result<Self, PlacementError> {
+        if self.symmetry_reduction && !queens.is_empty() {
+            return Err(PlacementError::SymmetryReduction);
+        }
+        let n = self.board_size;
+        for &(i, j) in queens {
+            if i >= n || j >= n {
+                return Err(PlacementError::OutOfBoard(i, j));
+            }
+            if self.fixed[i].is_some() {
+                return Err(PlacementError::SameRow(i));
+            }
+            if self.is_blocked(i, j) {
+                return Err(PlacementError::Blocked(i, j));
+            }
+            if let Some(other) = self
+                .fixed_queens()
+                .into_iter()
+                .find(|&other| self.attack((i, j), other))
+            {
+                return Err(PlacementError::Attack(other, (i, j)));
+            }
+            self.fixed[i] = Some(j);
+            self.occupy(i, j);
+        }
+        self.rebuild_links();
+        Ok(self)
+    }
+
+    // No queen can be placed on the blocked squares, given as (row, column),
+    // but they do not stop the attacks.
+    // Must be called before the search starts, and cannot be used with the
+    // symmetry reduction.
+    pub fn with_blocked_squares(
+        mut self,
+        squares: &[(usize, usize)],
+    ) -> Result<Self, PlacementError> {
+        if self.symmetry_reduction && !squares.is_empty() {
+            return Err(PlacementError::SymmetryReduction);
+        }
+        let n = self.board_size;
+        for &(i, j) in squares {
+            if i >= n || j >= n {
+                return Err(PlacementError::OutOfBoard(i, j));
+            }
+            if self.fixed[i] == Some(j) {
+                return Err(PlacementError::Blocked(i, j));
+            }
+            match &mut self.attacks {
+                Attacks::Vectors { blocked, .. } => {
+                    if blocked.is_empty() {
+                        blocked.resize(n * n, false);
+                    }
+                    blocked[i * n + j] = true;
+                }
+                Attacks::Bitboard { blocked, .. } => blocked[i] |= 1 << j,
+            }
+        }
+        self.rebuild_links();
+        Ok(self)
+    }
+
+    pub fn is_blocked(&self, i: usize, j: usize) -> bool {
+        match &self.attacks {
+            Attacks::Vectors { blocked, .. } => {
+                !blocked.is_empty() && blocked[i * self.board_size + j]
+            }
+            Attacks::Bitboard { blocked, .. } => blocked[i] & (1 << j) != 0,
+        }
+    }
+
+    pub fn blocked_squares(&self) -> Vec<(usize, usize)> {
+        let n = self.board_size;
+        (0..n * n)
+            .map(|k| (k / n, k % n))
+            .filter(|&(i, j)| self.is_blocked(i, j))
+            .collect()
+    }
+
+    // Must be called before the blocked squares and the fixed queens are given.
+    // If the variant has no solution for this size, the search is already over.
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        assert!(
+            self.fixed.iter().all(Option::is_none) && self.blocked_squares().is_empty(),
+            "the variant must be chosen before the fixed queens and the blocked squares"
+        );
+        self.variant = variant;
+        self.check_solutions();
+        self.rebuild_links();
+        self
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    // The pieces placed on the board, queens by default.
+    // Must be called before the blocked squares and the fixed queens are given.
+    pub fn with_piece(mut self, piece: Piece) -> Self {
+        assert!(
+            self.fixed.iter().all(Option::is_none) && self.blocked_squares().is_empty(),
+            "the piece must be chosen before the fixed queens and the blocked squares"
+        );
+        self.leap_offsets = piece.leap_offsets();
+        self.piece = piece;
+        self.check_solutions();
+        self.rebuild_links();
+        self
+    }
+
+    // Before the search starts, ends it at once if the variant and the piece
+    // have no solution for this size, or starts it again otherwise
+    fn check_solutions(&mut self) {
+        self.context.clear();
+        if self.variant.has_solutions(self.board_size, &self.piece) {
+            self.context.push((0, 0));
+        }
+    }
+
+    pub fn piece(&self) -> &Piece {
+        &self.piece
+    }
+
+    // The columns of each row are tried in the given order, which is computed
+    // again each time the search enters the row. The random order is the same
+    // for the same seed.
+    // Must be called before the search starts, after the other options.
+    pub fn with_value_order(mut self, order: ValueOrder) -> Self {
+        assert!(
+            self.range_start.is_empty() && self.range_end.is_empty(),
+            "a bounded search must try the columns from left to right"
+        );
+        assert!(
+            order == ValueOrder::LeftToRight || self.links.is_none(),
+            "the dancing links choose the order of the search themselves"
+        );
+        let n = self.board_size;
+        self.column_order = match order {
+            ValueOrder::LeftToRight => None,
+            _ => Some(ColumnOrder {
+                order,
+                random: Random::new(match order {
+                    ValueOrder::Random(seed) => seed,
+                    _ => 0,
+                }),
+                columns: vec![Vec::new(); n],
+                positions: vec![Vec::new(); n],
+            }),
+        };
+        self.free_lines = if order == ValueOrder::LeastConstraining && self.leap_offsets.is_empty()
+        {
+            Some(self.count_free_lines())
+        } else {
+            None
+        };
+        self.restart_context();
+        self
+    }
+
+    pub fn value_order(&self) -> ValueOrder {
+        self.column_order
+            .as_ref()
+            .map_or(ValueOrder::LeftToRight, |order| order.order)
+    }
+
+    // The rows are filled in the given order, which does not change the
+    // solutions found, only the order in which they are found.
+    // Must be called before the search starts, after the other options.
+    pub fn with_row_order(mut self, order: RowOrder) -> Self {
+        assert!(
+            order == RowOrder::TopDown || self.range_start.is_empty() && self.range_end.is_empty(),
+            "a bounded search must fill the rows from top to bottom"
+        );
+        assert!(
+            order == RowOrder::TopDown || self.links.is_none(),
+            "the dancing links choose the order of the search themselves"
+        );
+        self.row_order = order;
+        self.free_counts = if order == RowOrder::MostConstrained && self.leap_offsets.is_empty() {
+            (0..self.board_size).map(|i| self.free_columns(i)).collect()
+        } else {
+            Vec::new()
+        };
+        self.restart_context();
+        self
+    }
+
+    pub fn row_order(&self) -> RowOrder {
+        self.row_order
+    }
+
+    // Only the placements whose first rows lie between `start` and `end` in
+    // lexicographic order, both included, are explored. For instance, with
+    // [3, 0] and [5, 7], the first two queens go from (0, 3), (1, 0) to
+    // (0, 5), (1, 7). The bounds can have different lengths, and an empty
+    // bound does not restrict the search.
+    // Must be called before the search starts, and cannot be used with a
+    // randomized search.
+    pub fn with_prefix_range(
+        mut self,
+        start: &[usize],
+        end: &[usize],
+    ) -> Result<Self, PlacementError> {
+        assert!(
+            self.column_order.is_none()
+                && self.row_order == RowOrder::TopDown
+                && self.links.is_none(),
+            "a bounded search must fill the rows from top to bottom, from left to right"
+        );
+        let n = self.board_size;
+        for bound in &[start, end] {
+            if bound.len() > n {
+                return Err(PlacementError::OutOfBoard(bound.len() - 1, 0));
+            }
+            if let Some((i, &j)) = bound.iter().enumerate().find(|&(_, &j)| j >= n) {
+                return Err(PlacementError::OutOfBoard(i, j));
+            }
+        }
+        self.range_start = start.to_vec();
+        self.range_end = end.to_vec();
+        self.restart_context();
+        Ok(self)
+    }
+
+    pub fn prefix_range(&self) -> (&[usize], &[usize]) {
+        (&self.range_start, &self.range_end)
+    }
+
+    // Counts the events of the search, which slows it down a little
+    pub fn with_statistics(mut self) -> Self {
+        self.statistics = Some(Statistics::new(self.board_size));
+        self
+    }
+
+    pub fn statistics(&self) -> Option<&Statistics> {
+        self.statistics.as_ref()
+    }
+
+    pub(crate) fn add_statistics(&mut self, statistics: &Statistics) {
+        if let Some(own) = &mut self.statistics {
+            own.merge(statistics);
+        }
+    }
+
+    pub fn fixed_queens(&self) -> Vec<(usize, usize)> {
+        self.fixed
+            .iter()
+            .enumerate()
+            .filter_map(|(i, j)| j.map(|j| (i, j)))
+            .collect()
+    }
+
+    // The queens on the board, as (row, column), in the order they were placed
+    pub fn queens(&self) -> Vec<(usize, usize)> {
+        self.context[..self.context.len().saturating_sub(1)].to_vec()
+    }
+
+    pub fn reduces_symmetries(&self) -> bool {
+        self.symmetry_reduction
+    }
+
+    pub fn board_size(&self) -> usize {
+        self.board_size
+    }
+
+    pub fn backend(&self) -> Backend {
+        match (&self.links, &self.attacks) {
+            (Some(_), _) => Backend::DancingLinks,
+            (None, Attacks::Vectors { .. }) => Backend::Vectors,
+            (None, Attacks::Bitboard { .. }) => Backend::Bitboard,
         }
     }
 
@@ -37,64 +878,1902 @@ impl Solver {
         self.finished
     }
 
+    // With the symmetry reduction, only counts the representatives
     pub fn solutions_found(&self) -> u64 {
         self.solutions_found
     }
 
-    fn backtrack_once(&mut self) {
-        self.context.pop();
-        match self.context.last().copied() {
-            Some((i, j)) => {
-                self.col[j] = true;
-                self.down[i + j] = true;
-                self.up[i + self.board_size - j - 1] = true;
-                let last_pos = self.context.len() - 1;
-                self.context[last_pos] = (i, j + 1);
+    // With the symmetry reduction, also counts the solutions equivalent to the
+    // representatives found. Otherwise, same as `solutions_found`.
+    pub fn total_solutions(&self) -> u64 {
+        self.total_solutions
+    }
+
+    // A solver with the same rules, whose search has not started
+    fn restarted(&self) -> Self {
+        let mut solver = self.clone();
+        // The queens placed by the dancing links do not occupy `attacks`
+        let placed = match solver.links {
+            Some(_) => 0,
+            None => solver.context.len().saturating_sub(1),
+        };
+        for k in 0..placed {
+            let (i, j) = solver.context[k];
+            if solver.fixed[i].is_none() {
+                solver.release(i, j);
             }
-            _ => (),
         }
+        solver.solutions_found = 0;
+        solver.total_solutions = 0;
+        solver.finished = false;
+        solver.root_depth = 0;
+        solver.backtrack_next = false;
+        solver.symmetry_order = None;
+        if solver.statistics.is_some() {
+            solver.statistics = Some(Statistics::new(solver.board_size));
+        }
+        solver.context.clear();
+        if solver
+            .variant
+            .has_solutions(solver.board_size, &solver.piece)
+        {
+            let cursor = solver.next_cursor();
+            solver.context.push(cursor);
+        }
+        solver.rebuild_links();
+        solver
     }
 
-    pub fn search(&mut self) -> Option<Configuration> {
-        match self.context.last().copied() {
-            Some((i, mut j)) => {
-                if i == self.board_size {
-                    self.backtrack_once();
-                    return self.search();
-                }
+    fn rebuild_links(&mut self) {
+        if self.links.is_some() {
+            self.build_links();
+        }
+    }
 
-                while j < self.board_size
-                    && !(self.col[j] && self.down[i + j] && self.up[i + self.board_size - j - 1])
-                {
-                    j += 1;
+    // The matrix of the dancing links has a primary item for each row, and for
+    // each column when the pieces attack along the columns, and a secondary
+    // item for each diagonal and for each pair of squares a leap apart. Each
+    // free square is an option covering its lines, and the fixed queens are
+    // selected before the search starts.
+    fn build_links(&mut self) {
+        let n = self.board_size;
+        let primary = if self.piece.columns { 2 * n } else { n };
+        let diagonals = match (self.piece.diagonals, self.variant) {
+            (false, _) => 0,
+            (true, Variant::Classic) => 2 * n - 1,
+            (true, Variant::Toroidal) => n,
+        };
+        let mut pairs = HashMap::new();
+        let mut options = Vec::new();
+        for i in 0..n {
+            for j in 0..n {
+                if self.is_blocked(i, j) || (self.symmetry_reduction && i == 0 && j > (n - 1) / 2) {
+                    continue;
                 }
+                let mut items = vec![i];
+                if self.piece.columns {
+                    items.push(n + j);
+                }
+                if self.piece.diagonals {
+                    let (d, u) = self.diagonals(i, j);
+                    items.push(primary + d);
+                    items.push(primary + diagonals + u);
+                }
+                for &offset in &self.leap_offsets {
+                    match self.leap((i, j), offset) {
+                        Some(square) if square.0 != i => {
+                            let pair = ((i, j).min(square), (i, j).max(square));
+                            let next = pairs.len();
+                            let pair = *pairs.entry(pair).or_insert(next);
+                            items.push(primary + 2 * diagonals + pair);
+                        }
+                        _ => (),
+                    }
+                }
+                // Different leaps can reach the same square on small toroidal
+                // boards
+                items.sort_unstable();
+                items.dedup();
+                options.push(((i, j), items));
+            }
+        }
 
-                if j == self.board_size {
-                    self.backtrack_once();
-                    return self.search();
+        let mut links = DancingLinks::new(primary, 2 * diagonals + pairs.len());
+        for (square, items) in &options {
+            links.add_option(*square, items);
+        }
+        let fixed = self.fixed_queens();
+        let solvable = fixed.iter().all(|&queen| links.select(queen));
+        if !self.context.is_empty() {
+            self.context = fixed;
+            self.context.push((n, 0));
+            if solvable {
+                links.start();
+            }
+        }
+        self.links = Some(links);
+    }
+
+    // Moves the cursor of a search which has not started yet, after a change
+    // of the order of the rows or the columns
+    fn restart_context(&mut self) {
+        if !self.context.is_empty() {
+            self.context.clear();
+            let cursor = self.next_cursor();
+            self.context.push(cursor);
+        }
+    }
+
+    // Checks that a placement, given by the column of each row, is a solution
+    // for the rules of this solver
+    pub fn verify(&self, configuration: &[usize]) -> bool {
+        configuration.len() == self.board_size && self.with_prefix(configuration).is_some()
+    }
+
+    // A solver restricted to the completions of the queens given for the first
+    // rows, which tries the columns in order. Returns `None` if these queens
+    // cannot be placed.
+    pub(crate) fn with_prefix(&self, prefix: &[usize]) -> Option<Solver> {
+        let mut solver = self.restarted();
+        solver.context.clear();
+        solver.links = None;
+        solver.column_order = None;
+        solver.row_order = RowOrder::TopDown;
+        solver.free_counts.clear();
+        solver.free_lines = None;
+        for (i, &j) in prefix.iter().enumerate() {
+            solver.context.push((i, j));
+            match solver.fixed[i] {
+                Some(column) if column != j => return None,
+                Some(_) => (),
+                None => {
+                    if j >= self.board_size || solver.next_free(i, j) != j {
+                        return None;
+                    }
+                    solver.occupy(i, j);
                 }
+            }
+        }
+        solver.context.push((prefix.len(), 0));
+        solver.root_depth = prefix.len();
+        Some(solver)
+    }
 
-                let last_pos = self.context.len() - 1;
-                self.context[last_pos] = (i, j);
-                self.col[j] = false;
-                self.down[i + j] = false;
-                self.up[i + self.board_size - j - 1] = false;
+    // Same as `with_prefix`, but the search is not restricted: its next
+    // solution is `solution`, and the solutions before it are counted as found
+    pub(crate) fn positioned_at(&self, solution: &[usize], found: u64, total: u64) -> Solver {
+        let n = self.board_size;
+        let mut solver = self
+            .with_prefix(&solution[..n - 1])
+            .expect("the solution must be valid");
+        solver.context[n - 1] = (n - 1, solution[n - 1]);
+        solver.root_depth = 0;
+        solver.solutions_found = found;
+        solver.total_solutions = total;
+        solver
+    }
 
-                let configuration = self.context.iter().map(|(_, j)| *j).collect();
+    // Number of solutions completing the queens given for the first rows, as
+    // (solutions found, total solutions) like `solutions_found` and
+    // `total_solutions`
+    pub fn count_completions(&self, prefix: &[usize]) -> (u64, u64) {
+        match self.with_prefix(prefix) {
+            Some(mut solver) => {
+                while solver.next_event() != SearchEvent::Exhausted {}
+                (solver.solutions_found, solver.total_solutions)
+            }
+            None => (0, 0),
+        }
+    }
 
-                self.context.push((i + 1, 0));
-                if i + 1 == self.board_size {
-                    self.solutions_found += 1;
+    // Draws `count` distinct solutions at random. Each one is the first new
+    // solution of a randomized search, with a seed taken from `seed`. Returns
+    // less solutions only if there are not enough.
+    pub fn sample_solutions(&self, count: usize, seed: u64) -> Vec<Configuration> {
+        let mut seeds = Random::new(seed);
+        let mut seen = HashSet::new();
+        let mut samples = Vec::new();
+        while samples.len() < count {
+            let mut solver = self
+                .restarted()
+                .with_value_order(ValueOrder::Random(seeds.next_u64()));
+            loop {
+                match solver.search() {
+                    Some(config) if config.is_valid => {
+                        if seen.insert(config.configuration.clone()) {
+                            samples.push(config);
+                            break;
+                        }
+                    }
+                    Some(_) => (),
+                    // This search went through all the solutions
+                    None => return samples,
                 }
-                Some(Configuration {
-                    configuration,
-                    is_valid: i + 1 == self.board_size,
+            }
+        }
+        samples
+    }
+
+    // The attacked lines are not saved: they are rebuilt from the placed queens
+    // when the checkpoint is restored.
+    pub fn save_checkpoint(&self, path: &Path) -> io::Result<()> {
+        if self.column_order.is_some()
+            || self.row_order != RowOrder::TopDown
+            || self.links.is_some()
+        {
+            return Err(io::Error::other(
+                "only the searches filling the rows from top to bottom, from left to right, can be saved",
+            ));
+        }
+        let positions = |positions: &[(usize, usize)]| {
+            positions
+                .iter()
+                .map(|(i, j)| format!("{},{}", i, j))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+        let columns = |columns: &[usize]| {
+            columns
+                .iter()
+                .map(|j| j.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        let content = format!(
+            "{}\nboard_size {}\nbackend {}\nvariant {}\npiece {}\nsymmetry_reduction {}\nsolutions_found {}\ntotal_solutions {}\nfinished {}\nroot_depth {}\nbacktrack_next {}\nblocked {}\nfixed {}\nrange_start {}\nrange_end {}\ncontext {}\n",
+            CHECKPOINT_HEADER,
+            self.board_size,
+            self.backend().to_str(),
+            self.variant.to_str(),
+            self.piece,
+            self.symmetry_reduction,
+            self.solutions_found,
+            self.total_solutions,
+            self.finished,
+            self.root_depth,
+            self.backtrack_next,
+            positions(&self.blocked_squares()),
+            positions(&self.fixed_queens()),
+            columns(&self.range_start),
+            columns(&self.range_end),
+            positions(&self.context)
+        );
+
+        // Write the whole file before replacing the previous checkpoint, so
+        // that a crash while saving does not lose it
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, content)?;
+        fs::rename(&temporary, path)
+    }
+
+    pub fn from_checkpoint(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut lines = content.lines();
+        if lines.next() != Some(CHECKPOINT_HEADER) {
+            return Err(invalid_checkpoint("not a checkpoint file"));
+        }
+        let fields = lines
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, ' ');
+                Some((parts.next()?, parts.next().unwrap_or("")))
+            })
+            .collect::<HashMap<&str, &str>>();
+        fn parse<T: FromStr>(fields: &HashMap<&str, &str>, name: &str) -> io::Result<T> {
+            fields
+                .get(name)
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| invalid_checkpoint(&format!("missing or invalid field '{}'", name)))
+        }
+
+        let board_size: usize = parse(&fields, "board_size")?;
+        let backend: Backend = parse(&fields, "backend")?;
+        if backend == Backend::DancingLinks {
+            return Err(invalid_checkpoint(
+                "the dancing links backend cannot be saved",
+            ));
+        }
+        if board_size == 0 || board_size > backend.max_board_size() {
+            return Err(invalid_checkpoint("invalid board size"));
+        }
+        let positions = |name: &str| {
+            fields
+                .get(name)
+                .map_or("", |value| *value)
+                .split_whitespace()
+                .map(|position| {
+                    let mut coordinates = position.splitn(2, ',').map(|x| x.parse::<usize>());
+                    match (coordinates.next(), coordinates.next()) {
+                        (Some(Ok(i)), Some(Ok(j))) => Ok((i, j)),
+                        _ => Err(invalid_checkpoint(&format!("invalid field '{}'", name))),
+                    }
+                })
+                .collect::<io::Result<Vec<(usize, usize)>>>()
+        };
+        if !fields.contains_key("context") {
+            return Err(invalid_checkpoint("missing field 'context'"));
+        }
+        let context = positions("context")?;
+        let blocked = positions("blocked")?;
+        let fixed = positions("fixed")?;
+        let columns = |name: &str| {
+            fields
+                .get(name)
+                .map_or("", |value| value.trim())
+                .split(',')
+                .filter(|column| !column.is_empty())
+                .map(|column| {
+                    column
+                        .parse::<usize>()
+                        .map_err(|_| invalid_checkpoint(&format!("invalid field '{}'", name)))
                 })
+                .collect::<io::Result<Vec<usize>>>()
+        };
+        let range_start = columns("range_start")?;
+        let range_end = columns("range_end")?;
+
+        let variant = match fields.get("variant") {
+            Some(_) => parse(&fields, "variant")?,
+            None => Variant::Classic,
+        };
+        let piece = match fields.get("piece") {
+            Some(_) => parse(&fields, "piece")?,
+            None => Piece::queen(),
+        };
+        let mut solver = Self::with_backend(board_size, backend)
+            .with_variant(variant)
+            .with_piece(piece)
+            .with_blocked_squares(&blocked)
+            .and_then(|solver| solver.with_fixed_queens(&fixed))
+            .and_then(|solver| solver.with_prefix_range(&range_start, &range_end))
+            .map_err(|error| invalid_checkpoint(&error.to_string()))?;
+        solver.symmetry_reduction = parse(&fields, "symmetry_reduction")?;
+        if solver.symmetry_reduction && !(fixed.is_empty() && blocked.is_empty()) {
+            return Err(invalid_checkpoint(
+                &PlacementError::SymmetryReduction.to_string(),
+            ));
+        }
+        solver.solutions_found = parse(&fields, "solutions_found")?;
+        solver.total_solutions = parse(&fields, "total_solutions")?;
+        solver.finished = parse(&fields, "finished")?;
+        solver.root_depth = parse(&fields, "root_depth")?;
+
+        // All positions but the last one are placed queens, on consecutive rows
+        let placed = context.len().saturating_sub(1);
+        if solver.root_depth > placed {
+            return Err(invalid_checkpoint("invalid root depth"));
+        }
+        // The context is rebuilt one queen at a time, so that the leaps of the
+        // queens already placed are taken into account
+        solver.context.clear();
+        for (k, &(i, j)) in context.iter().enumerate() {
+            let limit = if k < placed {
+                board_size
+            } else {
+                board_size + 1
+            };
+            if i != k || i >= limit || j >= limit {
+                return Err(invalid_checkpoint("invalid context"));
             }
-            None => {
-                self.finished = true;
-                None
+            solver.context.push((i, j));
+            if k < placed {
+                match solver.fixed[i] {
+                    Some(column) if column != j => {
+                        return Err(invalid_checkpoint("the context moves a fixed queen"))
+                    }
+                    Some(_) => (),
+                    None => {
+                        if solver.next_free(i, j) != j {
+                            return Err(invalid_checkpoint(
+                                "queens of the context attack each other",
+                            ));
+                        }
+                        solver.occupy(i, j);
+                    }
+                }
             }
         }
+        // Checkpoints saved before the search events were introduced always
+        // backtracked after a complete placement
+        solver.backtrack_next = match fields.get("backtrack_next") {
+            Some(_) => parse(&fields, "backtrack_next")?,
+            None => matches!(context.last(), Some(&(i, _)) if i == board_size),
+        };
+        solver.context = context;
+        Ok(solver)
+    }
+
+    // Whether pieces on these two squares attack each other. The rows are not
+    // taken into account, since there is always one piece per row.
+    pub fn attack(&self, (i1, j1): (usize, usize), (i2, j2): (usize, usize)) -> bool {
+        let (down1, up1) = self.diagonals(i1, j1);
+        let (down2, up2) = self.diagonals(i2, j2);
+        (self.piece.columns && j1 == j2)
+            || (self.piece.diagonals && (down1 == down2 || up1 == up2))
+            || self
+                .leap_offsets
+                .iter()
+                .any(|&offset| self.leap((i1, j1), offset) == Some((i2, j2)))
+    }
+
+    // The square reached from (i, j) by a leap, if it is on the board
+    fn leap(&self, (i, j): (usize, usize), (di, dj): (isize, isize)) -> Option<(usize, usize)> {
+        let n = self.board_size as isize;
+        let (i, j) = (i as isize + di, j as isize + dj);
+        match self.variant {
+            Variant::Toroidal => Some((i.rem_euclid(n) as usize, j.rem_euclid(n) as usize)),
+            Variant::Classic if 0 <= i && i < n && 0 <= j && j < n => {
+                Some((i as usize, j as usize))
+            }
+            Variant::Classic => None,
+        }
+    }
+
+    // The column of the queen of row `i`, if it is already placed or fixed
+    fn queen_column(&self, i: usize) -> Option<usize> {
+        match self.row_order {
+            RowOrder::TopDown if i + 1 < self.context.len() => Some(self.context[i].1),
+            RowOrder::TopDown => self.fixed[i],
+            RowOrder::MostConstrained => self.context[..self.context.len().saturating_sub(1)]
+                .iter()
+                .find(|&&(row, _)| row == i)
+                .map(|&(_, j)| j)
+                .or(self.fixed[i]),
+        }
+    }
+
+    fn is_leap_attacked(&self, i: usize, j: usize) -> bool {
+        self.leap_offsets
+            .iter()
+            .any(|&offset| match self.leap((i, j), offset) {
+                Some((i2, j2)) => i2 != i && self.queen_column(i2) == Some(j2),
+                None => false,
+            })
+    }
+
+    // Indices of the two diagonals going through (i, j)
+    fn diagonals(&self, i: usize, j: usize) -> (usize, usize) {
+        let n = self.board_size;
+        match (self.variant, &self.attacks) {
+            (Variant::Toroidal, _) => ((i + j) % n, (j + n - i) % n),
+            (Variant::Classic, Attacks::Vectors { .. }) => (i + j, i + n - j - 1),
+            (Variant::Classic, Attacks::Bitboard { .. }) => (i + j, j + n - 1 - i),
+        }
+    }
+
+    // Returns the first free column of row `i` starting from column `j`,
+    // or the board size if there is none.
+    fn next_free(&self, i: usize, j: usize) -> usize {
+        let mut j = self.next_free_line(i, j);
+        while j < self.board_size && self.is_leap_attacked(i, j) {
+            j = self.next_free_line(i, j + 1);
+        }
+        j
+    }
+
+    // Whether (i, j) is neither blocked nor attacked along the columns and
+    // the diagonals
+    fn is_line_free(&self, i: usize, j: usize) -> bool {
+        let n = self.board_size;
+        let (columns, diagonals) = (self.piece.columns, self.piece.diagonals);
+        match &self.attacks {
+            Attacks::Vectors {
+                col,
+                up,
+                down,
+                blocked,
+            } => {
+                let (d, u) = self.diagonals(i, j);
+                (!columns || col[j])
+                    && (!diagonals || (down[d] && up[u]))
+                    && (blocked.is_empty() || !blocked[i * n + j])
+            }
+            Attacks::Bitboard { .. } => self.next_free_line(i, j) == j,
+        }
+    }
+
+    // Same as `next_free`, but only looks at the columns and the diagonals
+    fn next_free_line(&self, i: usize, j: usize) -> usize {
+        let n = self.board_size;
+        let (columns, diagonals) = (self.piece.columns, self.piece.diagonals);
+        match &self.attacks {
+            Attacks::Vectors { .. } => {
+                let mut j = j;
+                while j < n && !self.is_line_free(i, j) {
+                    j += 1;
+                }
+                j
+            }
+            Attacks::Bitboard {
+                col,
+                up,
+                down,
+                blocked,
+            } => {
+                let full = if n == 64 { !0 } else { (1u64 << n) - 1 };
+                let from = (!0u64).checked_shl(j as u32).unwrap_or(0);
+                let mut attacked = blocked[i];
+                if columns {
+                    attacked |= col;
+                }
+                if diagonals {
+                    attacked |= match self.variant {
+                        Variant::Classic => (up >> (n - 1 - i)) as u64 | (down >> i) as u64,
+                        Variant::Toroidal => {
+                            rotate_left(*up as u64, i, n) | rotate_right(*down as u64, i, n)
+                        }
+                    };
+                }
+                let free = !attacked & full & from;
+                if free == 0 {
+                    n
+                } else {
+                    free.trailing_zeros() as usize
+                }
+            }
+        }
+    }
+
+    // Whether the queens of the rows before `i` are on the columns of `bound`
+    fn follows(&self, i: usize, bound: &[usize]) -> bool {
+        self.context[..i]
+            .iter()
+            .zip(bound)
+            .all(|(&(_, j), &column)| j == column)
+    }
+
+    // Whether a queen on (i, j) puts the first rows after the end of the range
+    fn is_past_range_end(&self, i: usize, j: usize) -> bool {
+        i < self.range_end.len() && j > self.range_end[i] && self.follows(i, &self.range_end)
+    }
+
+    // Next position of the cursor, once the queens of `context` are placed:
+    // the next row to fill and the first column tried on it, or
+    // (board_size, 0) when all the rows are filled
+    fn next_cursor(&mut self) -> (usize, usize) {
+        let placed = self.context.len();
+        if placed >= self.board_size {
+            return (self.board_size, 0);
+        }
+        let i = match self.row_order {
+            RowOrder::TopDown => placed,
+            RowOrder::MostConstrained => self.most_constrained_row(),
+        };
+        (i, self.first_column(i))
+    }
+
+    // Whether each row holds one of the queens of `context`
+    fn filled_rows(&self) -> Vec<bool> {
+        let mut filled = vec![false; self.board_size];
+        for &(i, _) in &self.context {
+            if i < self.board_size {
+                filled[i] = true;
+            }
+        }
+        filled
+    }
+
+    // The empty row with the fewest free columns, the one nearest to an edge
+    // if several, which leads the search into fewer dead ends
+    fn most_constrained_row(&self) -> usize {
+        let filled = self.filled_rows();
+        (0..self.board_size)
+            .filter(|&i| !filled[i])
+            .min_by_key(|&i| {
+                let count = match self.fixed[i] {
+                    Some(_) => 1,
+                    None if !self.free_counts.is_empty() => self.free_counts[i],
+                    None => self.free_columns(i),
+                };
+                (count, Reverse((2 * i).abs_diff(self.board_size - 1)))
+            })
+            .unwrap()
+    }
+
+    fn free_columns(&self, i: usize) -> usize {
+        let mut count = 0;
+        let mut j = self.next_free(i, 0);
+        while j < self.board_size {
+            count += 1;
+            j = self.next_free(i, j + 1);
+        }
+        count
+    }
+
+    fn is_free(&self, i: usize, j: usize) -> bool {
+        self.is_line_free(i, j) && !self.is_leap_attacked(i, j)
+    }
+
+    // The squares on the column and the diagonals of (i, j) attacked by the
+    // piece, at most three per row
+    fn line_squares(&self, i: usize, j: usize) -> Vec<(usize, usize)> {
+        let n = self.board_size as isize;
+        let mut squares = Vec::new();
+        for row in 0..self.board_size {
+            let d = row as isize - i as isize;
+            let mut columns = Vec::with_capacity(3);
+            if self.piece.columns {
+                columns.push(j as isize);
+            }
+            if self.piece.diagonals {
+                columns.push(j as isize + d);
+                columns.push(j as isize - d);
+            }
+            let mut row_squares = Vec::with_capacity(3);
+            for column in columns {
+                let column = match self.variant {
+                    Variant::Toroidal => column.rem_euclid(n),
+                    Variant::Classic => column,
+                };
+                if 0 <= column && column < n && !row_squares.contains(&(row, column as usize)) {
+                    row_squares.push((row, column as usize));
+                }
+            }
+            squares.extend(row_squares);
+        }
+        squares
+    }
+
+    // Number of free squares of the `empty` rows attacked by a queen on (i, j)
+    fn constraint(&self, i: usize, j: usize, empty: &[bool]) -> usize {
+        let n = self.board_size as isize;
+        let mut squares = Vec::new();
+        for (row, _) in empty
+            .iter()
+            .enumerate()
+            .filter(|&(row, &empty)| empty && row != i)
+        {
+            let d = row as isize - i as isize;
+            for &column in &[j as isize, j as isize + d, j as isize - d] {
+                let column = match self.variant {
+                    Variant::Toroidal => column.rem_euclid(n),
+                    Variant::Classic => column,
+                };
+                if 0 <= column && column < n {
+                    squares.push((row, column as usize));
+                }
+            }
+        }
+        for &offset in &self.leap_offsets {
+            if let Some((row, column)) = self.leap((i, j), offset) {
+                if empty[row] && row != i {
+                    squares.push((row, column));
+                }
+            }
+        }
+        squares.sort_unstable();
+        squares.dedup();
+        squares
+            .into_iter()
+            .filter(|&square| self.attack((i, j), square) && self.is_free(square.0, square.1))
+            .count()
+    }
+
+    // First column tried on row `i`, computing the order of the row when the
+    // columns are not tried from left to right. Under the symmetry reduction,
+    // the left half of the first row comes first, so that the search can stop
+    // at the other half.
+    fn first_column(&mut self, i: usize) -> usize {
+        if i < self.range_start.len() && self.follows(i, &self.range_start) {
+            return self.range_start[i];
+        }
+        let n = self.board_size;
+        let order = match &self.column_order {
+            Some(order) if i < n && self.fixed[i].is_none() => order.order,
+            _ => return 0,
+        };
+        let mut columns = (0..n).collect::<Vec<usize>>();
+        match order {
+            ValueOrder::LeftToRight => (),
+            ValueOrder::MiddleOut => columns.sort_by_key(|&j| (2 * j).abs_diff(n - 1)),
+            ValueOrder::Random(_) => {
+                if let Some(order) = &mut self.column_order {
+                    order.random.shuffle(&mut columns);
+                }
+            }
+            ValueOrder::LeastConstraining => {
+                let filled = self.filled_rows();
+                let empty = (0..n)
+                    .map(|row| !filled[row] && self.fixed[row].is_none())
+                    .collect::<Vec<bool>>();
+                // The columns which are not free come last
+                let costs = columns
+                    .iter()
+                    .map(|&j| {
+                        if !self.is_free(i, j) {
+                            usize::MAX
+                        } else if self.free_lines.is_some() {
+                            self.attacked_free_squares(i, j)
+                        } else {
+                            self.constraint(i, j, &empty)
+                        }
+                    })
+                    .collect::<Vec<usize>>();
+                columns.sort_by_key(|&j| (costs[j], (2 * j).abs_diff(n - 1)));
+            }
+        }
+        if self.symmetry_reduction && i == 0 {
+            columns.sort_by_key(|&j| j > (n - 1) / 2);
+        }
+
+        let mut positions = vec![0; n];
+        for (k, &j) in columns.iter().enumerate() {
+            positions[j] = k;
+        }
+        let order = self.column_order.as_mut().unwrap();
+        order.columns[i] = columns;
+        order.positions[i] = positions;
+        order.columns[i][0]
+    }
+
+    // Column tried on row `i` after column `j`, or `board_size` if none
+    fn following_column(&self, i: usize, j: usize) -> usize {
+        match &self.column_order {
+            Some(order) if self.fixed[i].is_none() => {
+                let k = order.positions[i][j] + 1;
+                order.columns[i].get(k).copied().unwrap_or(self.board_size)
+            }
+            _ => j + 1,
+        }
+    }
+
+    // Same as `next_free`, but follows the order of the columns of the row
+    fn next_free_in_order(&self, i: usize, j: usize) -> usize {
+        let order = match &self.column_order {
+            Some(order) => order,
+            None => return self.next_free(i, j),
+        };
+        if j == self.board_size {
+            return j;
+        }
+        order.columns[i][order.positions[i][j]..]
+            .iter()
+            .copied()
+            .find(|&column| self.is_free(i, column))
+            .unwrap_or(self.board_size)
+    }
+
+    fn occupy(&mut self, i: usize, j: usize) {
+        if self.free_lines.is_some() {
+            self.update_free_lines(i, j, true);
+        }
+        // The free squares of the lines of the queen become attacked
+        if !self.free_counts.is_empty() {
+            for (row, column) in self.line_squares(i, j) {
+                if self.is_line_free(row, column) {
+                    self.free_counts[row] -= 1;
+                }
+            }
+        }
+        let (d, u) = self.diagonals(i, j);
+        match &mut self.attacks {
+            Attacks::Vectors { col, up, down, .. } => {
+                col[j] = false;
+                down[d] = false;
+                up[u] = false;
+            }
+            Attacks::Bitboard { col, up, down, .. } => {
+                *col |= 1 << j;
+                *down |= 1 << d;
+                *up |= 1 << u;
+            }
+        }
+    }
+
+    fn release(&mut self, i: usize, j: usize) {
+        let (d, u) = self.diagonals(i, j);
+        match &mut self.attacks {
+            Attacks::Vectors { col, up, down, .. } => {
+                col[j] = true;
+                down[d] = true;
+                up[u] = true;
+            }
+            Attacks::Bitboard { col, up, down, .. } => {
+                *col &= !(1 << j);
+                *down &= !(1 << d);
+                *up &= !(1 << u);
+            }
+        }
+        if !self.free_counts.is_empty() {
+            for (row, column) in self.line_squares(i, j) {
+                if self.is_line_free(row, column) {
+                    self.free_counts[row] += 1;
+                }
+            }
+        }
+        if self.free_lines.is_some() {
+            self.update_free_lines(i, j, false);
+        }
+    }
+
+    // The free squares of the empty rows, counted on their lines
+    fn count_free_lines(&self) -> FreeLines {
+        let n = self.board_size;
+        let mut lines = FreeLines {
+            columns: vec![0; n],
+            down: vec![0; 2 * n],
+            up: vec![0; 2 * n],
+            filled: self.fixed.iter().map(Option::is_some).collect(),
+        };
+        for &(i, _) in &self.context[..self.context.len().saturating_sub(1)] {
+            lines.filled[i] = true;
+        }
+        for i in 0..n {
+            if lines.filled[i] {
+                continue;
+            }
+            for j in (0..n).filter(|&j| self.is_line_free(i, j)) {
+                let (d, u) = self.diagonals(i, j);
+                lines.columns[j] += 1;
+                lines.down[d] += 1;
+                lines.up[u] += 1;
+            }
+        }
+        lines
+    }
+
+    // Called before a queen is placed on (i, j), or once it is lifted: the
+    // free squares of its row, and the ones of its lines on the other empty
+    // rows, leave or come back to the counts
+    fn update_free_lines(&mut self, i: usize, j: usize, placed: bool) {
+        let filled = &self.free_lines.as_ref().unwrap().filled;
+        let mut squares = (0..self.board_size)
+            .filter(|&column| self.is_line_free(i, column))
+            .map(|column| (i, column))
+            .collect::<Vec<(usize, usize)>>();
+        squares.extend(
+            self.line_squares(i, j)
+                .into_iter()
+                .filter(|&(row, column)| {
+                    row != i && !filled[row] && self.is_line_free(row, column)
+                }),
+        );
+        let diagonals = squares
+            .iter()
+            .map(|&(row, column)| self.diagonals(row, column))
+            .collect::<Vec<(usize, usize)>>();
+        let lines = self.free_lines.as_mut().unwrap();
+        for (&(_, column), &(d, u)) in squares.iter().zip(&diagonals) {
+            if placed {
+                lines.columns[column] -= 1;
+                lines.down[d] -= 1;
+                lines.up[u] -= 1;
+            } else {
+                lines.columns[column] += 1;
+                lines.down[d] += 1;
+                lines.up[u] += 1;
+            }
+        }
+        lines.filled[i] = placed;
+    }
+
+    // Number of free squares of the other empty rows attacked by a queen on
+    // the free square (i, j) of an empty row, from the counts of its lines
+    fn attacked_free_squares(&self, i: usize, j: usize) -> usize {
+        let n = self.board_size;
+        let lines = self.free_lines.as_ref().unwrap();
+        let (d, u) = self.diagonals(i, j);
+        // (i, j) is on each of its lines
+        let mut count = 0;
+        if self.piece.columns {
+            count += lines.columns[j] - 1;
+        }
+        if self.piece.diagonals {
+            count += lines.down[d] - 1 + lines.up[u] - 1;
+            // The diagonals of even toroidal boards cross again half a board
+            // away
+            let (row, column) = ((i + n / 2) % n, (j + n / 2) % n);
+            if self.variant == Variant::Toroidal
+                && n.is_multiple_of(2)
+                && !lines.filled[row]
+                && self.is_line_free(row, column)
+            {
+                count -= 1;
+            }
+        }
+        count
+    }
+
+    pub(crate) fn add_solutions(&mut self, found: u64, total: u64) {
+        self.solutions_found += found;
+        self.total_solutions += total;
+    }
+
+    // A copy of the solver which only explores the continuations of the queens
+    // on the board, from the first one, and then is finished. The search of
+    // `self` is not changed. The counters go on from the ones of `self`.
+    // On a solution already given, the fork starts by backtracking, so that
+    // the solution is not counted again, and has nothing left to explore.
+    pub fn fork(&self) -> Solver {
+        let mut fork = self.clone();
+        if fork.context.is_empty() {
+            return fork;
+        }
+        fork.finished = false;
+        fork.backtrack_next =
+            self.backtrack_next && self.context.last().map(|&(i, _)| i) == Some(self.board_size);
+        fork.context.pop();
+        fork.root_depth = fork.context.len();
+        match &mut fork.links {
+            Some(links) => {
+                links.keep_selected();
+                links.restart_level();
+                fork.context.push((fork.board_size, 0));
+            }
+            None => {
+                let cursor = fork.next_cursor();
+                fork.context.push(cursor);
+            }
+        }
+        fork
+    }
+
+    // Explores the placements of `depth` more queens than the ones which are
+    // never backtracked, not counting the fixed queens, and returns a fork for
+    // each of them, with its own counters.
+    // The solutions with less queens are counted by `self`, which has no work
+    // left afterwards.
+    pub fn split(&mut self, depth: usize) -> Vec<Solver> {
+        let root_depth = self.root_depth;
+        let mut subtrees = Vec::new();
+        while let Some(placement) = self.next_placement() {
+            // The complete placements have no subtree
+            let placed = placement.queens.len();
+            if placed == self.board_size {
+                continue;
+            }
+            let free = self.context[root_depth.min(placed)..placed]
+                .iter()
+                .filter(|&&(i, _)| self.fixed[i].is_none())
+                .count();
+            if free == depth {
+                let mut subtree = self.fork();
+                subtree.solutions_found = 0;
+                subtree.total_solutions = 0;
+                if subtree.statistics.is_some() {
+                    subtree.statistics = Some(Statistics::new(self.board_size));
+                }
+                subtrees.push(subtree);
+                // The queen lifted is counted here since the subtree never
+                // lifts it
+                let lifted = self.backtrack_once();
+                if let (Some((i, j)), Some(statistics)) = (lifted, &mut self.statistics) {
+                    let fixed = self.fixed[i].is_some();
+                    statistics.record(SearchEvent::Remove(i, j), placed - 1, fixed);
+                }
+            }
+        }
+        subtrees
+    }
+
+    // Splits the remaining work into at most `parts` solvers bounded by
+    // consecutive prefix ranges, with about the same number of prefixes each.
+    // The first solver goes on with the search of `self`, along with the
+    // solutions already found, and the others start from scratch.
+    pub fn split_ranges(&self, parts: usize) -> Vec<Solver> {
+        assert!(
+            self.column_order.is_none()
+                && self.row_order == RowOrder::TopDown
+                && self.links.is_none(),
+            "the ranges need the rows filled from top to bottom, from left to right"
+        );
+        let n = self.board_size;
+        let parts = parts.max(1);
+        if n < 2 || self.context.is_empty() {
+            return vec![self.clone()];
+        }
+        let mut depth = 1;
+        let mut prefixes = self.remaining_prefixes(depth);
+        while prefixes.len() < PREFIXES_PER_RANGE * parts && depth + 1 < n {
+            depth += 1;
+            prefixes = self.remaining_prefixes(depth);
+        }
+        if prefixes.is_empty() {
+            return vec![self.clone()];
+        }
+
+        // A bound of the current range which is longer than the prefixes
+        // is kept when the prefix is on it
+        let bound = |prefix: &[usize], current: &[usize]| {
+            if current.len() > prefix.len() && current[..prefix.len()] == *prefix {
+                current.to_vec()
+            } else {
+                prefix.to_vec()
+            }
+        };
+        let count = prefixes.len();
+        let parts = parts.min(count);
+        (0..parts)
+            .map(|k| {
+                let first = &prefixes[k * count / parts];
+                let last = &prefixes[(k + 1) * count / parts - 1];
+                let mut solver = if k == 0 {
+                    self.clone()
+                } else {
+                    let mut solver = self.restarted();
+                    solver.range_start = bound(first, &self.range_start);
+                    solver.restart_context();
+                    solver
+                };
+                solver.range_end = bound(last, &self.range_end);
+                solver
+            })
+            .collect()
+    }
+
+    // The prefixes of `depth` rows left to explore, in lexicographic order.
+    // The first one can already be partly explored.
+    fn remaining_prefixes(&self, depth: usize) -> Vec<Vec<usize>> {
+        let mut solver = self.clone();
+        let mut prefixes = Vec::new();
+        if solver.context.len() > depth {
+            prefixes.push(solver.context[..depth].iter().map(|&(_, j)| j).collect());
+            while solver.context.len() > depth {
+                solver.backtrack_once();
+            }
+            solver.backtrack_next = false;
+        }
+        while let Some(placement) = solver.next_placement() {
+            if placement.queens.len() == depth {
+                prefixes.push(placement.queens.iter().map(|&(_, j)| j).collect());
+                solver.backtrack_once();
+            }
+        }
+        prefixes
+    }
+
+    // Returns the queen lifted by the backtrack, if any
+    fn backtrack_once(&mut self) -> Option<(usize, usize)> {
+        if let Some(links) = &mut self.links {
+            return match links.backtrack_once() {
+                Some(queen) => {
+                    let last_pos = self.context.len() - 2;
+                    self.context.remove(last_pos);
+                    Some(queen)
+                }
+                None => {
+                    self.context.clear();
+                    None
+                }
+            };
+        }
+        self.context.pop();
+        if self.context.len() <= self.root_depth {
+            self.context.clear();
+        }
+        let (i, j) = self.context.last().copied()?;
+        if self.fixed[i].is_none() {
+            self.release(i, j);
+        }
+        let last_pos = self.context.len() - 1;
+        self.context[last_pos] = (i, self.following_column(i, j));
+        Some((i, j))
+    }
+
+    fn is_solution(&mut self) -> bool {
+        self.symmetry_order = None;
+        if self.symmetry_reduction {
+            let (configuration, _) = self.placement();
+            self.symmetry_order = symmetry::representative_order(&configuration);
+            self.total_solutions += self.symmetry_order.unwrap_or(0) as u64;
+            self.symmetry_order.is_some()
+        } else {
+            self.total_solutions += 1;
+            true
+        }
+    }
+
+    // Advances the search by one event. Once the search is exhausted, always
+    // returns `Exhausted`.
+    pub fn next_event(&mut self) -> SearchEvent {
+        let event = if self.links.is_some() {
+            self.next_links_event()
+        } else {
+            self.next_backtracking_event()
+        };
+        if self.statistics.is_some() {
+            let depth = self.context.len().saturating_sub(1);
+            let fixed = matches!(
+                event,
+                SearchEvent::Place(i, _) | SearchEvent::Remove(i, _) if self.fixed[i].is_some()
+            );
+            if let Some(statistics) = &mut self.statistics {
+                statistics.record(event, depth, fixed);
+            }
+        }
+        event
+    }
+
+    fn next_backtracking_event(&mut self) -> SearchEvent {
+        let (i, j) = match self.context.last().copied() {
+            Some(position) => position,
+            None => {
+                self.finished = true;
+                return SearchEvent::Exhausted;
+            }
+        };
+
+        if !self.backtrack_next {
+            if i == self.board_size {
+                self.backtrack_next = true;
+                if self.is_solution() {
+                    self.solutions_found += 1;
+                    return SearchEvent::Solution;
+                }
+            } else {
+                let mut j = match self.fixed[i] {
+                    Some(column) if j <= column => column,
+                    Some(_) => self.board_size,
+                    None => self.next_free_in_order(i, j),
+                };
+                if self.symmetry_reduction && i == 0 && j > (self.board_size - 1) / 2 {
+                    j = self.board_size;
+                }
+                if j < self.board_size && self.is_past_range_end(i, j) {
+                    j = self.board_size;
+                }
+
+                if j == self.board_size {
+                    self.backtrack_next = true;
+                    return SearchEvent::DeadEnd(i);
+                }
+
+                let last_pos = self.context.len() - 1;
+                self.context[last_pos] = (i, j);
+                if self.fixed[i].is_none() {
+                    self.occupy(i, j);
+                }
+                let cursor = self.next_cursor();
+                self.context.push(cursor);
+                return SearchEvent::Place(i, j);
+            }
+        }
+
+        self.backtrack_next = false;
+        match self.backtrack_once() {
+            Some((i, j)) => SearchEvent::Remove(i, j),
+            None => {
+                self.finished = true;
+                SearchEvent::Exhausted
+            }
+        }
+    }
+
+    // Same as `next_event`, with the dancing links backend
+    fn next_links_event(&mut self) -> SearchEvent {
+        let links = self.links.as_mut().unwrap();
+        match links.next_step() {
+            Step::Select((i, j)) => {
+                let last_pos = self.context.len() - 1;
+                self.context.insert(last_pos, (i, j));
+                SearchEvent::Place(i, j)
+            }
+            Step::Deselect((i, j)) => {
+                let last_pos = self.context.len() - 2;
+                self.context.remove(last_pos);
+                SearchEvent::Remove(i, j)
+            }
+            Step::DeadEnd(item) if item < self.board_size => SearchEvent::DeadEnd(item),
+            Step::DeadEnd(item) => SearchEvent::ColumnDeadEnd(item - self.board_size),
+            Step::Solution => {
+                if self.is_solution() {
+                    self.solutions_found += 1;
+                    SearchEvent::Solution
+                } else {
+                    self.next_links_event()
+                }
+            }
+            Step::Exhausted => {
+                self.context.clear();
+                self.finished = true;
+                SearchEvent::Exhausted
+            }
+        }
+    }
+
+    // The columns of the queens placed, row after row, along with their rows
+    // if they are not the first ones in order
+    fn placement(&self) -> (Vec<usize>, Option<Vec<usize>>) {
+        columns_and_rows(&self.context[..self.context.len() - 1], self.board_size)
+    }
+
+    // Gives the next placement of a queen, borrowed from the solver
+    pub fn next_placement(&mut self) -> Option<Placement<'_>> {
+        loop {
+            match self.next_event() {
+                SearchEvent::Place(_, _) => {
+                    let placed = self.context.len() - 1;
+                    if placed < self.board_size {
+                        return Some(Placement {
+                            queens: &self.context[..placed],
+                            is_valid: false,
+                            symmetry_order: None,
+                            board_size: self.board_size,
+                        });
+                    }
+                    self.complete.clear();
+                    self.complete.extend_from_slice(&self.context[..placed]);
+                    // The event following the last queen tells whether the
+                    // placement is reported as a solution
+                    let is_valid = self.next_event() == SearchEvent::Solution;
+                    return Some(Placement {
+                        queens: &self.complete,
+                        is_valid,
+                        symmetry_order: if is_valid { self.symmetry_order } else { None },
+                        board_size: self.board_size,
+                    });
+                }
+                SearchEvent::Exhausted => return None,
+                _ => (),
+            }
+        }
+    }
+
+    // Same as `next_placement`, with an owned copy of the placement
+    pub fn search(&mut self) -> Option<Configuration> {
+        self.next_placement()
+            .map(|placement| placement.to_configuration())
+    }
+
+    // Same as `next_placement` for counting: once a placement has at least
+    // `depth` queens, the solutions below it are counted at once with bit
+    // masks, without going through the events, when the options allow it.
+    // Returns false once the search is over.
+    pub fn count_next_subtree(&mut self, depth: usize) -> bool {
+        let placed = match self.next_placement() {
+            Some(placement) => placement.queens.len(),
+            None => return false,
+        };
+        if placed >= depth && placed < self.board_size && self.counts_with_masks(placed) {
+            let found = self.masks(placed).total();
+            self.solutions_found += found;
+            self.total_solutions += found;
+            // The subtree is over, as if it had been explored
+            self.backtrack_next = true;
+        }
+        true
+    }
+
+    // Whether the solutions below the `placed` queens of the context can be
+    // counted with the masks of the empty rows: the pieces only attack along
+    // lines, and neither the symmetries, the statistics nor the bounds of the
+    // range need to see each solution
+    fn counts_with_masks(&self, placed: usize) -> bool {
+        self.links.is_none()
+            && self.board_size <= 64
+            && self.leap_offsets.is_empty()
+            && !self.symmetry_reduction
+            && self.statistics.is_none()
+            && !(placed < self.range_start.len() && self.follows(placed, &self.range_start))
+            && !(placed < self.range_end.len() && self.follows(placed, &self.range_end))
+    }
+
+    // The rows without any of the `placed` queens of the context or a fixed
+    // queen, with the squares attacked on them
+    fn masks(&self, placed: usize) -> MaskCounter {
+        let n = self.board_size;
+        let mut filled = vec![false; n];
+        for &(i, _) in &self.context[..placed] {
+            filled[i] = true;
+        }
+        let empty = (0..n)
+            .filter(|&i| !filled[i] && self.fixed[i].is_none())
+            .collect::<Vec<usize>>();
+        let rows = empty
+            .iter()
+            .enumerate()
+            .map(|(k, &i)| {
+                let attacked = (0..n)
+                    .filter(|&j| !self.is_line_free(i, j))
+                    .fold(0u64, |mask, j| mask | 1 << j);
+                // Less than the board size, even on toroidal boards
+                let distance = empty.get(k + 1).map_or(0, |&next| next - i);
+                (attacked, distance)
+            })
+            .collect();
+        MaskCounter::new(rows, n, self.variant, &self.piece)
+    }
+}
+
+// Counts the placements of one queen on each of the rows, given the squares
+// already attacked on them
+pub(crate) struct MaskCounter {
+    // For each row, from top to bottom: the squares attacked, and the number
+    // of rows to the next one
+    rows: Vec<(u64, usize)>,
+    board_size: usize,
+    full: u64,
+    toroidal: bool,
+    // Full if the pieces attack along these lines, empty otherwise
+    columns: u64,
+    diagonals: u64,
+    // Placements from each row of the first half, by row, columns and
+    // diagonals attacked on it, when the counts are kept
+    memo: Option<HashMap<(usize, u64, u64, u64), u64>>,
+}
+
+impl MaskCounter {
+    // The number of rows to the next one must be less than the board size
+    pub(crate) fn new(
+        rows: Vec<(u64, usize)>,
+        board_size: usize,
+        variant: Variant,
+        piece: &Piece,
+    ) -> Self {
+        let full = if board_size == 64 {
+            !0
+        } else {
+            (1u64 << board_size) - 1
+        };
+        Self {
+            rows,
+            board_size,
+            full,
+            toroidal: variant == Variant::Toroidal,
+            columns: if piece.columns { full } else { 0 },
+            diagonals: if piece.diagonals { full } else { 0 },
+            memo: None,
+        }
+    }
+
+    // Keeps the counts, for the counters queried many times. The last rows
+    // are counted again, faster than their many situations would be looked
+    // up.
+    pub(crate) fn with_memo(mut self) -> Self {
+        self.memo = Some(HashMap::new());
+        self
+    }
+
+    fn total(&mut self) -> u64 {
+        if self.rows.is_empty() {
+            1
+        } else {
+            self.count(0, 0, 0, 0)
+        }
+    }
+
+    // The squares of the `k`-th row which can hold a queen, given the columns
+    // and the diagonals attacked on it by the queens of the rows before
+    pub(crate) fn free(&self, k: usize, cols: u64, left: u64, right: u64) -> u64 {
+        !(self.rows[k].0 | cols | left | right) & self.full
+    }
+
+    // The columns and the diagonals attacked on the row after the `k`-th one,
+    // once a queen is on the square `bit` of it
+    pub(crate) fn place(
+        &self,
+        k: usize,
+        bit: u64,
+        cols: u64,
+        left: u64,
+        right: u64,
+    ) -> (u64, u64, u64) {
+        let distance = self.rows[k].1;
+        let (left, right) = (left | bit, right | bit);
+        let (left, right) = if self.toroidal {
+            (
+                rotate_left(left, distance, self.board_size),
+                rotate_right(right, distance, self.board_size),
+            )
+        } else {
+            ((left << distance) & self.full, right >> distance)
+        };
+        (
+            (cols | bit) & self.columns,
+            left & self.diagonals,
+            right & self.diagonals,
+        )
+    }
+
+    // The placements from the `k`-th row, given the columns and the diagonals
+    // attacked on it by the queens of the rows before
+    pub(crate) fn count(&mut self, k: usize, cols: u64, left: u64, right: u64) -> u64 {
+        let mut free = self.free(k, cols, left, right);
+        if k + 1 == self.rows.len() {
+            return free.count_ones() as u64;
+        }
+        let memoized = 2 * k < self.rows.len();
+        if memoized {
+            if let Some(&count) = self
+                .memo
+                .as_ref()
+                .and_then(|memo| memo.get(&(k, cols, left, right)))
+            {
+                return count;
+            }
+        }
+        let mut count = 0;
+        while free != 0 {
+            let bit = free & free.wrapping_neg();
+            free &= free - 1;
+            let (cols, left, right) = self.place(k, bit, cols, left, right);
+            count += self.count(k + 1, cols, left, right);
+        }
+        if memoized {
+            if let Some(memo) = &mut self.memo {
+                memo.insert((k, cols, left, right), count);
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(mut solver: Solver) -> u64 {
+        while solver.next_placement().is_some() {}
+        solver.solutions_found()
+    }
+
+    // A file of the temporary directory for the checkpoints of a test
+    fn checkpoint_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("n-queens-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn small_boards_without_queens_have_solutions() {
+        for &backend in &[Backend::Vectors, Backend::Bitboard, Backend::DancingLinks] {
+            let solver = |n, piece| Solver::with_backend(n, backend).with_piece(piece);
+            assert_eq!(count(solver(2, Piece::rook())), 2);
+            assert_eq!(count(solver(3, Piece::rook())), 6);
+            assert_eq!(count(solver(2, Piece::bishop())), 2);
+            assert_eq!(count(solver(3, Piece::bishop())), 5);
+            assert_eq!(count(solver(3, Piece::queen())), 0);
+            let toroidal = Solver::with_backend(4, backend)
+                .with_variant(Variant::Toroidal)
+                .with_piece(Piece::rook());
+            assert_eq!(count(toroidal), 24);
+            let toroidal = Solver::with_backend(4, backend)
+                .with_piece(Piece::rook())
+                .with_variant(Variant::Toroidal);
+            assert_eq!(count(toroidal), 24);
+        }
+    }
+
+    #[test]
+    fn fixed_queens_are_rejected_with_the_symmetry_reduction() {
+        let solver = Solver::new(8).with_symmetry_reduction();
+        assert_eq!(
+            solver.with_fixed_queens(&[(0, 5)]).err(),
+            Some(PlacementError::SymmetryReduction)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the fixed queens break the symmetries")]
+    fn symmetry_reduction_is_refused_after_fixed_queens() {
+        Solver::new(8)
+            .with_fixed_queens(&[(0, 5)])
+            .unwrap()
+            .with_symmetry_reduction();
+    }
+
+    #[test]
+    fn blocked_squares_are_rejected_with_the_symmetry_reduction() {
+        let solver = Solver::new(8).with_symmetry_reduction();
+        assert_eq!(
+            solver.with_blocked_squares(&[(2, 3)]).err(),
+            Some(PlacementError::SymmetryReduction)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the blocked squares break the symmetries")]
+    fn symmetry_reduction_is_refused_after_blocked_squares() {
+        Solver::new(8)
+            .with_blocked_squares(&[(2, 3)])
+            .unwrap()
+            .with_symmetry_reduction();
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        let error = |options: &SolverOptions| Solver::try_new(options).err();
+        let mut options = SolverOptions::new(8);
+        options.symmetry_reduction = true;
+        options.fixed_queens = vec![(0, 5)];
+        assert_eq!(error(&options), Some(SolverError::BrokenSymmetry));
+        options.fixed_queens.clear();
+        options.blocked_squares = vec![(2, 3)];
+        assert_eq!(error(&options), Some(SolverError::BrokenSymmetry));
+
+        let mut options = SolverOptions::new(8);
+        options.fixed_queens = vec![(0, 0), (3, 3)];
+        assert_eq!(
+            error(&options),
+            Some(SolverError::InvalidPlacement(PlacementError::Attack(
+                (0, 0),
+                (3, 3)
+            )))
+        );
+        options.fixed_queens = vec![(2, 3)];
+        options.blocked_squares = vec![(2, 3)];
+        assert_eq!(
+            error(&options),
+            Some(SolverError::InvalidPlacement(PlacementError::Blocked(2, 3)))
+        );
+        options.blocked_squares = vec![(2, 4)];
+        assert!(Solver::try_new(&options).is_ok());
+    }
+
+    #[test]
+    fn sizes_beyond_the_backend_are_rejected() {
+        for backend in [Backend::Vectors, Backend::Bitboard, Backend::DancingLinks] {
+            for board_size in [backend.max_board_size() + 1, 100_000_000_000] {
+                let mut options = SolverOptions::new(board_size);
+                options.backend = backend;
+                assert_eq!(
+                    Solver::try_new(&options).err(),
+                    Some(SolverError::TooLarge(board_size, backend))
+                );
+            }
+        }
+        assert_eq!(
+            SolverOptions::new(100_000_000_000).backend,
+            Backend::Vectors
+        );
+    }
+
+    #[test]
+    fn forks_count_the_continuations_once() {
+        for &backend in &[Backend::Vectors, Backend::Bitboard, Backend::DancingLinks] {
+            let mut solver = Solver::with_backend(8, backend);
+            while solver.next_event() != SearchEvent::Solution {}
+            let found = solver.solutions_found();
+            assert_eq!(count(solver.fork()), found);
+
+            let mut solver = Solver::with_backend(8, backend);
+            while solver.context.len() < 3 {
+                solver.next_event();
+            }
+            let fixed = Solver::with_backend(8, backend)
+                .with_fixed_queens(&solver.queens())
+                .unwrap();
+            let found = solver.solutions_found();
+            assert_eq!(count(solver.fork()) - found, count(fixed));
+        }
+    }
+
+    #[test]
+    fn subtrees_counted_with_masks_have_all_the_solutions() {
+        for &variant in &[Variant::Classic, Variant::Toroidal] {
+            for piece in &[Piece::queen(), Piece::rook(), Piece::bishop()] {
+                let solver = Solver::with_backend(7, Backend::Bitboard)
+                    .with_variant(variant)
+                    .with_piece(piece.clone())
+                    .with_blocked_squares(&[(3, 2)])
+                    .unwrap();
+                let expected = count(solver.clone());
+                for depth in 0..=7 {
+                    let mut solver = solver.clone();
+                    while solver.count_next_subtree(depth) {}
+                    assert_eq!(solver.solutions_found(), expected);
+                    assert_eq!(solver.total_solutions(), expected);
+                }
+            }
+        }
+        let mut solver = Solver::with_backend(12, Backend::Vectors)
+            .with_fixed_queens(&[(5, 3)])
+            .unwrap()
+            .with_row_order(RowOrder::MostConstrained);
+        while solver.count_next_subtree(0) {}
+        assert_eq!(solver.solutions_found(), 1127);
+    }
+
+    #[test]
+    fn split_ranges_counted_with_masks_add_up() {
+        for n in 4..=10 {
+            let total = count(Solver::new(n));
+            for parts in [2, 3, 4, 7] {
+                let found = Solver::new(n)
+                    .split_ranges(parts)
+                    .into_iter()
+                    .map(|mut range| {
+                        while range.count_next_subtree(0) {}
+                        range.solutions_found()
+                    })
+                    .sum::<u64>();
+                assert_eq!(found, total, "{} queens in {} ranges", n, parts);
+            }
+        }
+    }
+
+    fn solutions(mut solver: Solver) -> Vec<Vec<usize>> {
+        let mut solutions = Vec::new();
+        while let Some(placement) = solver.search() {
+            if placement.is_valid {
+                solutions.push(placement.configuration);
+            }
+        }
+        solutions
+    }
+
+    #[test]
+    fn samples_are_distinct_solutions() {
+        let solvers = [
+            Solver::new(8),
+            Solver::with_backend(8, Backend::Bitboard).with_symmetry_reduction(),
+            Solver::new(9).with_fixed_queens(&[(4, 2)]).unwrap(),
+            Solver::new(7).with_variant(Variant::Toroidal),
+        ];
+        for solver in &solvers {
+            let all = solutions(solver.clone());
+            let mut firsts = HashSet::new();
+            for seed in 0..5 {
+                let samples = solver.sample_solutions(5, seed);
+                firsts.insert(samples[0].configuration.clone());
+                assert_eq!(samples.len(), 5);
+                let configurations = samples
+                    .iter()
+                    .map(|sample| sample.configuration.clone())
+                    .collect::<HashSet<_>>();
+                assert_eq!(configurations.len(), 5);
+                for configuration in &configurations {
+                    assert!(solver.verify(configuration));
+                    assert!(all.contains(configuration));
+                }
+                let again = solver.sample_solutions(5, seed);
+                assert!(samples
+                    .iter()
+                    .zip(&again)
+                    .all(|(sample, again)| sample.configuration == again.configuration));
+            }
+            // The seeds lead to other solutions
+            assert!(firsts.len() > 1);
+            // Asking for more samples than solutions gives each solution once
+            let samples = solver.sample_solutions(all.len() + 10, 3);
+            let mut configurations = samples
+                .into_iter()
+                .map(|sample| sample.configuration)
+                .collect::<Vec<_>>();
+            configurations.sort();
+            let mut all = all;
+            all.sort();
+            assert_eq!(configurations, all);
+        }
+        assert!(Solver::new(3).sample_solutions(2, 0).is_empty());
+    }
+
+    // The counts of the three ways to go through the solutions of a range
+    fn range_counts(solver: &Solver) -> [u64; 3] {
+        let mut masks = solver.clone();
+        while masks.count_next_subtree(0) {}
+        [
+            solutions(solver.clone()).len() as u64,
+            masks.solutions_found(),
+            crate::parallel::count_solutions(&mut solver.clone(), 2),
+        ]
+    }
+
+    #[test]
+    fn split_ranges_cover_the_search_once() {
+        for n in 4..=9 {
+            let expected = solutions(Solver::new(n));
+            for parts in [1, 2, 3, 5, 8] {
+                let ranges = Solver::new(n).split_ranges(parts);
+                assert!(ranges.len() <= parts);
+                let mut found = Vec::new();
+                for range in ranges {
+                    let solutions = solutions(range.clone());
+                    assert_eq!(range_counts(&range), [solutions.len() as u64; 3]);
+                    found.extend(solutions);
+                }
+                // In order, so without overlap
+                assert_eq!(found, expected, "{} queens in {} ranges", n, parts);
+            }
+        }
+    }
+
+    #[test]
+    fn ranges_near_the_edges_of_the_board() {
+        let n = 7;
+        let all = solutions(Solver::new(n));
+        let bounds: [(&[usize], &[usize]); 10] = [
+            (&[], &[]),
+            (&[0], &[0]),
+            (&[6], &[]),
+            (&[], &[0, 0]),
+            (&[0, 0], &[0, 6]),
+            (&[6, 6], &[]),
+            (&[0, 6], &[6, 0]),
+            (&[2, 6], &[4, 0]),
+            (&[0, 2, 4, 6, 1, 3, 5], &[0, 2, 4, 6, 1, 3, 5]),
+            (&[6, 4, 2, 0, 5, 3, 1], &[6, 6, 6, 6, 6, 6, 6]),
+        ];
+        for (start, end) in bounds {
+            let expected = all
+                .iter()
+                .filter(|solution| {
+                    start <= &solution[..start.len()] && &solution[..end.len()] <= end
+                })
+                .cloned()
+                .collect::<Vec<Vec<usize>>>();
+            let range = Solver::new(n).with_prefix_range(start, end).unwrap();
+            assert_eq!(solutions(range.clone()), expected, "{:?}:{:?}", start, end);
+            assert_eq!(range_counts(&range), [expected.len() as u64; 3]);
+        }
+    }
+
+    #[test]
+    fn least_constraining_counts_follow_the_queens() {
+        for &variant in &[Variant::Classic, Variant::Toroidal] {
+            for piece in &[Piece::queen(), Piece::rook(), Piece::bishop()] {
+                let mut solver = Solver::with_backend(8, Backend::Vectors)
+                    .with_variant(variant)
+                    .with_piece(piece.clone())
+                    .with_blocked_squares(&[(3, 2)])
+                    .and_then(|solver| solver.with_fixed_queens(&[(6, 5)]))
+                    .unwrap()
+                    .with_value_order(ValueOrder::LeastConstraining)
+                    .with_row_order(RowOrder::MostConstrained);
+                while solver.next_event() != SearchEvent::Exhausted {
+                    let i = solver.context.last().unwrap().0;
+                    if i == 8 || solver.fixed[i].is_some() {
+                        continue;
+                    }
+                    let filled = solver.filled_rows();
+                    let empty = (0..8)
+                        .map(|row| !filled[row] && solver.fixed[row].is_none())
+                        .collect::<Vec<bool>>();
+                    for j in (0..8).filter(|&j| solver.is_free(i, j)) {
+                        assert_eq!(
+                            solver.attacked_free_squares(i, j),
+                            solver.constraint(i, j, &empty)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn least_constraining_finds_a_first_solution_of_200_rows_at_once() {
+        let mut solver = Solver::with_backend(200, Backend::Vectors)
+            .with_value_order(ValueOrder::LeastConstraining)
+            .with_row_order(RowOrder::MostConstrained);
+        let mut events = 0;
+        while solver.next_event() != SearchEvent::Solution {
+            events += 1;
+            assert!(events < 1000, "too many steps before the first solution");
+        }
+    }
+
+    #[test]
+    fn large_boards_are_searched_with_a_small_stack() {
+        let search = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(|| {
+                let mut solver = Solver::with_backend(1000, Backend::Vectors)
+                    .with_value_order(ValueOrder::MiddleOut)
+                    .with_row_order(RowOrder::MostConstrained);
+                let mut solution = solver.search().unwrap();
+                while !solution.is_valid {
+                    solution = solver.search().unwrap();
+                }
+                // Each rook has a single free square but on the first row, and
+                // the last row is blocked: a single step backtracks from the
+                // last row to the first one
+                let mut blocked = (0..1000).map(|j| (999, j)).collect::<Vec<_>>();
+                blocked.extend((1..999).map(|j| (0, j)));
+                for i in 1..999 {
+                    blocked.extend((0..1000).filter(|&j| j != i).map(|j| (i, j)));
+                }
+                let mut solver = Solver::with_backend(1000, Backend::Vectors)
+                    .with_piece(Piece::rook())
+                    .with_blocked_squares(&blocked)
+                    .unwrap();
+                while solver.search().is_some() {}
+                assert_eq!(solver.solutions_found(), 0);
+                solution
+            })
+            .unwrap();
+        let solution = search.join().unwrap();
+        let mut columns = vec![0; 1000];
+        for (i, j) in solution.queens() {
+            columns[i] = j;
+        }
+        assert!(Solver::new(1000).verify(&columns));
+    }
+
+    #[test]
+    fn checkpoints_resume_the_search() {
+        let path = checkpoint_path("resume");
+        let solvers = [
+            Solver::with_backend(8, Backend::Vectors).with_symmetry_reduction(),
+            Solver::with_backend(8, Backend::Bitboard)
+                .with_fixed_queens(&[(2, 4), (5, 0)])
+                .unwrap(),
+            Solver::with_backend(8, Backend::Vectors)
+                .with_variant(Variant::Classic)
+                .with_piece(Piece::rook())
+                .with_blocked_squares(&[(0, 0), (3, 5), (7, 2)])
+                .unwrap(),
+        ];
+        for solver in &solvers {
+            let mut expected = solver.clone();
+            while expected.next_placement().is_some() {}
+            for events in &[0, 1, 25, 200] {
+                let mut solver = solver.clone();
+                for _ in 0..*events {
+                    solver.next_event();
+                }
+                solver.save_checkpoint(&path).unwrap();
+                let mut restored = Solver::from_checkpoint(&path).unwrap();
+                assert_eq!(restored.queens(), solver.queens());
+                assert_eq!(restored.reduces_symmetries(), solver.reduces_symmetries());
+                assert_eq!(restored.fixed_queens(), solver.fixed_queens());
+                assert_eq!(restored.blocked_squares(), solver.blocked_squares());
+                while restored.next_placement().is_some() {}
+                assert_eq!(restored.solutions_found(), expected.solutions_found());
+                assert_eq!(restored.total_solutions(), expected.total_solutions());
+            }
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn invalid_checkpoints_are_rejected() {
+        let path = checkpoint_path("invalid");
+        let mut solver = Solver::with_backend(8, Backend::Bitboard)
+            .with_fixed_queens(&[(6, 3)])
+            .unwrap();
+        for _ in 0..12 {
+            solver.next_event();
+        }
+        solver.save_checkpoint(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        let replace = |field: &str, value: &str| {
+            content
+                .lines()
+                .map(|line| match line.split(' ').next() {
+                    Some(name) if name == field => format!("{} {}", field, value),
+                    _ => line.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        let invalid = [
+            // Malformed
+            String::new(),
+            content.replace(CHECKPOINT_HEADER, "n-queens checkpoint 0"),
+            replace("solutions_found", "many"),
+            replace("backend", "Abacus"),
+            replace("context", "0,1 1;3"),
+            replace("fixed", "6"),
+            // Truncated
+            content[..content.len() / 2].to_string(),
+            content[..content.len() - 3].to_string(),
+            content
+                .lines()
+                .filter(|line| !line.starts_with("context"))
+                .collect::<Vec<&str>>()
+                .join("\n"),
+            // Wrong sizes
+            replace("board_size", "0"),
+            replace("board_size", "5"),
+            replace("board_size", "65"),
+            replace("root_depth", "9"),
+            // Inconsistent with the rules
+            replace("context", "0,0 1,1 2,0"),
+            replace("context", "0,3 1,0"),
+            replace("fixed", "6,3 7,3"),
+            replace("symmetry_reduction", "true"),
+        ];
+        for content in &invalid {
+            fs::write(&path, content).unwrap();
+            let error = Solver::from_checkpoint(&path).err().expect(content);
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", content);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fixed_queens_are_checked_against_the_ones_fixed_before() {
+        let solver = Solver::new(8).with_fixed_queens(&[(0, 0)]).unwrap();
+        assert_eq!(
+            solver.with_fixed_queens(&[(3, 3)]).err(),
+            Some(PlacementError::Attack((0, 0), (3, 3)))
+        );
     }
 }
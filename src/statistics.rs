@@ -0,0 +1,185 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::solver::SearchEvent;
+
+// Counters of a search, updated by the solver at each event when they are
+// enabled with `Solver::with_statistics`
+#[derive(Clone, Debug)]
+pub struct Statistics {
+    // Nodes of the search tree entered below the root, one per queen placed,
+    // including the fixed queens
+    pub nodes: u64,
+    // Queens placed by the search, without the fixed queens
+    pub placements: u64,
+    // Queens lifted by the search, without the fixed queens
+    pub backtracks: u64,
+    pub solutions: u64,
+    // Number of times no other column of each row could hold a queen
+    pub dead_ends: Vec<u64>,
+    // Same for the columns, only filled by the dancing links backend
+    pub column_dead_ends: Vec<u64>,
+    // Largest number of queens on the board at the same time
+    pub max_depth: usize,
+    start: Option<Instant>,
+    // Set once the search is exhausted
+    elapsed: Option<Duration>,
+}
+
+impl Statistics {
+    pub fn new(board_size: usize) -> Self {
+        Self {
+            nodes: 0,
+            placements: 0,
+            backtracks: 0,
+            solutions: 0,
+            dead_ends: vec![0; board_size],
+            column_dead_ends: vec![0; board_size],
+            max_depth: 0,
+            start: None,
+            elapsed: None,
+        }
+    }
+
+    // `depth` is the number of queens on the board after the event, and
+    // `fixed` tells whether a queen placed or lifted is a fixed queen
+    pub fn record(&mut self, event: SearchEvent, depth: usize, fixed: bool) {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        match event {
+            SearchEvent::Place(_, _) => {
+                self.nodes += 1;
+                if !fixed {
+                    self.placements += 1;
+                }
+            }
+            SearchEvent::Remove(_, _) => {
+                if !fixed {
+                    self.backtracks += 1;
+                }
+            }
+            SearchEvent::DeadEnd(i) => self.dead_ends[i] += 1,
+            SearchEvent::ColumnDeadEnd(j) => self.column_dead_ends[j] += 1,
+            SearchEvent::Solution => self.solutions += 1,
+            SearchEvent::Exhausted => {
+                if self.elapsed.is_none() {
+                    self.elapsed = Some(start.elapsed());
+                }
+            }
+        }
+        self.max_depth = self.max_depth.max(depth);
+    }
+
+    // Time from the first event to the end of the search, or to now if the
+    // search is not over
+    pub fn elapsed(&self) -> Duration {
+        match (self.elapsed, self.start) {
+            (Some(elapsed), _) => elapsed,
+            (None, Some(start)) => start.elapsed(),
+            (None, None) => Duration::from_secs(0),
+        }
+    }
+
+    // Adds the counters of a search run apart on a part of the tree, for
+    // instance on another thread. The elapsed time runs until now.
+    pub fn merge(&mut self, other: &Statistics) {
+        self.nodes += other.nodes;
+        self.placements += other.placements;
+        self.backtracks += other.backtracks;
+        self.solutions += other.solutions;
+        for (count, other) in self.dead_ends.iter_mut().zip(&other.dead_ends) {
+            *count += other;
+        }
+        for (count, other) in self
+            .column_dead_ends
+            .iter_mut()
+            .zip(&other.column_dead_ends)
+        {
+            *count += other;
+        }
+        self.max_depth = self.max_depth.max(other.max_depth);
+        let start = *self.start.get_or_insert_with(Instant::now);
+        self.elapsed = Some(start.elapsed());
+    }
+
+    // The statistics as a JSON object on a single line
+    pub fn to_json(&self) -> String {
+        let list = |counts: &[u64]| {
+            counts
+                .iter()
+                .map(|count| count.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        format!(
+            "{{\"nodes\":{},\"placements\":{},\"backtracks\":{},\"solutions\":{},\"dead_ends\":[{}],\"column_dead_ends\":[{}],\"max_depth\":{},\"elapsed_seconds\":{}}}",
+            self.nodes,
+            self.placements,
+            self.backtracks,
+            self.solutions,
+            list(&self.dead_ends),
+            list(&self.column_dead_ends),
+            self.max_depth,
+            self.elapsed().as_secs_f64()
+        )
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Nodes visited: {}", self.nodes)?;
+        writeln!(f, "Queens placed: {}", self.placements)?;
+        writeln!(f, "Backtracks: {}", self.backtracks)?;
+        writeln!(f, "Dead ends per row: {:?}", self.dead_ends)?;
+        if self.column_dead_ends.iter().any(|&count| count > 0) {
+            writeln!(f, "Dead ends per column: {:?}", self.column_dead_ends)?;
+        }
+        writeln!(f, "Maximum depth: {}", self.max_depth)?;
+        write!(f, "Elapsed time: {:.3} s", self.elapsed().as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parallel;
+    use crate::solver::{Backend, Solver};
+
+    #[test]
+    fn fixed_queens_are_only_counted_as_nodes() {
+        let mut statistics = Statistics::new(4);
+        statistics.record(SearchEvent::Place(0, 1), 1, true);
+        statistics.record(SearchEvent::Place(1, 3), 2, false);
+        statistics.record(SearchEvent::DeadEnd(2), 2, false);
+        statistics.record(SearchEvent::Remove(1, 3), 1, false);
+        statistics.record(SearchEvent::Remove(0, 1), 0, true);
+        assert_eq!(statistics.nodes, 2);
+        assert_eq!(statistics.placements, 1);
+        assert_eq!(statistics.backtracks, 1);
+        assert_eq!(statistics.dead_ends, vec![0, 0, 1, 0]);
+        assert_eq!(statistics.max_depth, 2);
+    }
+
+    #[test]
+    fn every_queen_placed_is_lifted() {
+        for &backend in &[Backend::Vectors, Backend::Bitboard, Backend::DancingLinks] {
+            for fixed in [&[][..], &[(3, 0)][..], &[(3, 0), (6, 4)][..]] {
+                let solver = Solver::with_backend(8, backend)
+                    .with_fixed_queens(fixed)
+                    .unwrap()
+                    .with_statistics();
+                let mut sequential = solver.clone();
+                while sequential.next_placement().is_some() {}
+                let mut threads = solver.clone();
+                parallel::count_solutions(&mut threads, 3);
+                for solver in &[sequential, threads] {
+                    let statistics = solver.statistics().unwrap();
+                    assert_eq!(statistics.backtracks, statistics.placements);
+                    assert_eq!(statistics.solutions, solver.solutions_found());
+                    if fixed.is_empty() {
+                        assert_eq!(statistics.nodes, statistics.placements);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,20 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::parallel;
 use crate::solver::*;
 use crate::strategy::*;
 
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
 pub struct CountSolutions {
-    _params: StrategyParameters,
+    params: StrategyParameters,
 }
 
 impl Strategy for CountSolutions {
     fn new(params: StrategyParameters) -> Self {
-        Self { _params: params }
+        Self { params }
     }
 
     fn has_next_batch(&mut self, solver: &mut Solver) -> bool {
@@ -15,7 +22,56 @@ impl Strategy for CountSolutions {
     }
 
     fn next_step(&mut self, solver: &mut Solver) -> Option<Configuration> {
-        while let Some(_) = solver.search() {}
+        if self.params.threads > 1 {
+            parallel::count_solutions(solver, self.params.threads);
+        } else if let Some(path) = &self.params.checkpoint {
+            count_with_checkpoints(solver, path, CHECKPOINT_INTERVAL);
+        } else {
+            while solver.count_next_subtree(0) {}
+        }
         None
     }
 }
+
+// Counts the solutions, saving a checkpoint at each interval and at the end.
+// Returns the number of checkpoints saved.
+fn count_with_checkpoints(solver: &mut Solver, path: &Path, interval: Duration) -> u32 {
+    let mut last_save = Instant::now();
+    let mut saves = 0;
+    // Small enough subtrees to check the clock regularly, which costs little
+    // next to counting a subtree
+    let depth = solver.board_size() / 3;
+    while solver.count_next_subtree(depth) {
+        if last_save.elapsed() >= interval {
+            save_checkpoint(solver, path);
+            saves += 1;
+            last_save = Instant::now();
+        }
+    }
+    save_checkpoint(solver, path);
+    saves + 1
+}
+
+fn save_checkpoint(solver: &Solver, path: &Path) {
+    if let Err(error) = solver.save_checkpoint(path) {
+        eprintln!("Could not save the checkpoint: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoints_are_saved_during_the_count() {
+        let path = std::env::temp_dir().join(format!("n-queens-{}-count", std::process::id()));
+        let mut solver = Solver::new(13);
+        let saves = count_with_checkpoints(&mut solver, &path, Duration::from_millis(1));
+        assert!(saves > 1);
+        assert_eq!(solver.solutions_found(), 73712);
+        let restored = Solver::from_checkpoint(&path).unwrap();
+        assert!(restored.is_finished());
+        assert_eq!(restored.solutions_found(), 73712);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
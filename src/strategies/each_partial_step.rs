@@ -1,8 +1,12 @@
 use crate::solver::*;
 use crate::strategy::*;
 
+#[derive(Clone)]
 pub struct EachPartialStep {
     in_batch: bool,
+    // Solutions already found when the strategy started, for instance when
+    // resuming a search
+    solutions_before: Option<u64>,
     params: StrategyParameters,
 }
 
@@ -10,14 +14,18 @@ impl Strategy for EachPartialStep {
     fn new(params: StrategyParameters) -> Self {
         Self {
             in_batch: false,
+            solutions_before: None,
             params,
         }
     }
 
     fn has_next_batch(&mut self, solver: &mut Solver) -> bool {
         self.in_batch = false;
-        !(self.params.stop_after_first_solution && solver.solutions_found() > 0)
-            && !solver.is_finished()
+        let solutions_before = *self
+            .solutions_before
+            .get_or_insert(solver.solutions_found());
+        !(solver.is_finished()
+            || self.params.stop_after_first_solution && solver.solutions_found() > solutions_before)
     }
 
     fn next_step(&mut self, solver: &mut Solver) -> Option<Configuration> {
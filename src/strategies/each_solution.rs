@@ -1,8 +1,12 @@
 use crate::solver::*;
 use crate::strategy::*;
 
+#[derive(Clone)]
 pub struct EachSolution {
     in_batch: bool,
+    // Solutions already found when the strategy started, for instance when
+    // resuming a search
+    solutions_before: Option<u64>,
     params: StrategyParameters,
 }
 
@@ -10,25 +14,29 @@ impl Strategy for EachSolution {
     fn new(params: StrategyParameters) -> Self {
         Self {
             in_batch: false,
+            solutions_before: None,
             params,
         }
     }
 
     fn has_next_batch(&mut self, solver: &mut Solver) -> bool {
         self.in_batch = false;
-        !(self.params.stop_after_first_solution && solver.solutions_found() > 0)
-            && !solver.is_finished()
+        let solutions_before = *self
+            .solutions_before
+            .get_or_insert(solver.solutions_found());
+        !(solver.is_finished()
+            || self.params.stop_after_first_solution && solver.solutions_found() > solutions_before)
     }
 
     fn next_step(&mut self, solver: &mut Solver) -> Option<Configuration> {
         if !self.in_batch {
             self.in_batch = true;
-            while let Some(config) = solver.search() {
-                if config.is_valid == true {
-                    return Some(config);
+            while let Some(placement) = solver.next_placement() {
+                if placement.is_valid {
+                    return Some(placement.to_configuration());
                 }
             }
-            return None;
+            None
         } else {
             None
         }
@@ -1,8 +1,12 @@
+use std::path::PathBuf;
+
 use crate::solver::{Configuration, Solver};
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct StrategyParameters {
     pub stop_after_first_solution: bool,
+    pub threads: usize,
+    pub checkpoint: Option<PathBuf>,
 }
 
 pub trait Strategy {
@@ -0,0 +1,97 @@
+// A placement is given by the column of the queen of each row.
+// Returns the images of the placement by the 8 symmetries of the square,
+// starting with the identity.
+pub fn images(configuration: &[usize]) -> Vec<Vec<usize>> {
+    let n = configuration.len();
+    let mut images = vec![vec![0; n]; 8];
+    for (i, &j) in configuration.iter().enumerate() {
+        images[0][i] = j;
+        images[1][j] = n - 1 - i;
+        images[2][n - 1 - i] = n - 1 - j;
+        images[3][n - 1 - j] = i;
+        images[4][i] = n - 1 - j;
+        images[5][n - 1 - i] = j;
+        images[6][j] = i;
+        images[7][n - 1 - j] = n - 1 - i;
+    }
+    images
+}
+
+fn distinct_images(images: &mut Vec<Vec<usize>>) -> u8 {
+    images.sort();
+    images.dedup();
+    images.len() as u8
+}
+
+// The number of distinct solutions in the equivalence class of a solution:
+// 1, 2, 4 or 8.
+pub fn symmetry_order(solution: &[usize]) -> u8 {
+    distinct_images(&mut images(solution))
+}
+
+// A solution is the representative of its equivalence class if it is the
+// smallest of its images in lexicographic order.
+// Returns the symmetry order of the solution if it is a representative.
+pub fn representative_order(solution: &[usize]) -> Option<u8> {
+    let mut images = images(solution);
+    if images.iter().any(|image| image[..] < *solution) {
+        None
+    } else {
+        Some(distinct_images(&mut images))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{Backend, Solver};
+    use std::collections::HashSet;
+
+    // A000170, the solutions of the n-queens problem, and A002562, the
+    // solutions up to the symmetries of the square, from n = 1
+    const SOLUTIONS: [u64; 12] = [1, 0, 0, 2, 10, 4, 40, 92, 352, 724, 2680, 14200];
+    const FUNDAMENTAL_SOLUTIONS: [u64; 12] = [1, 0, 0, 1, 2, 1, 6, 12, 46, 92, 341, 1787];
+
+    fn solutions(mut solver: Solver) -> HashSet<Vec<usize>> {
+        let mut solutions = HashSet::new();
+        let reduced = solver.reduces_symmetries();
+        while let Some(placement) = solver.search() {
+            if placement.is_valid {
+                if reduced {
+                    assert_eq!(
+                        placement.symmetry_order,
+                        representative_order(&placement.configuration)
+                    );
+                }
+                solutions.insert(placement.configuration);
+            }
+        }
+        solutions
+    }
+
+    #[test]
+    fn reduced_counts_follow_the_sequences() {
+        for backend in [Backend::Vectors, Backend::Bitboard, Backend::DancingLinks] {
+            for n in 1..=12 {
+                let mut solver = Solver::with_backend(n, backend).with_symmetry_reduction();
+                while solver.next_placement().is_some() {}
+                assert_eq!(solver.solutions_found(), FUNDAMENTAL_SOLUTIONS[n - 1]);
+                assert_eq!(solver.total_solutions(), SOLUTIONS[n - 1]);
+            }
+        }
+    }
+
+    #[test]
+    fn images_of_the_representatives_are_all_the_solutions() {
+        for n in 1..=12 {
+            let representatives = solutions(Solver::new(n).with_symmetry_reduction());
+            assert_eq!(representatives.len() as u64, FUNDAMENTAL_SOLUTIONS[n - 1]);
+            let images = representatives
+                .iter()
+                .flat_map(|representative| images(representative))
+                .collect::<HashSet<_>>();
+            assert_eq!(images.len() as u64, SOLUTIONS[n - 1]);
+            assert_eq!(images, solutions(Solver::new(n)));
+        }
+    }
+}